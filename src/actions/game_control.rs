@@ -1,35 +1,302 @@
-use bevy::prelude::{Input, KeyCode, Res};
+use bevy::prelude::{GamepadButtonType, Input, KeyCode, Resource};
+use serde::{Deserialize, Serialize};
 
-pub enum GameControl {
-    Up,
-    Down,
-    Left,
-    Right,
+use super::GameAction;
+
+/// Keys that can be held together with the bound key
+const MODIFIERS: [KeyCode; 6] = [
+    KeyCode::ControlLeft,
+    KeyCode::ControlRight,
+    KeyCode::ShiftLeft,
+    KeyCode::ShiftRight,
+    KeyCode::AltLeft,
+    KeyCode::AltRight,
+];
+
+/// Key, optionally held together with a modifier, that triggers an action
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KeyBinding {
+    pub modifier: Option<KeyCode>,
+    pub key: KeyCode,
+    pub action: GameAction,
 }
 
-impl GameControl {
-    pub fn pressed(&self, keyboard_input: &Res<Input<KeyCode>>) -> bool {
-        match self {
-            GameControl::Up => {
-                keyboard_input.pressed(KeyCode::W) || keyboard_input.pressed(KeyCode::Up)
-            }
-            GameControl::Down => {
-                keyboard_input.pressed(KeyCode::S) || keyboard_input.pressed(KeyCode::Down)
+impl KeyBinding {
+    const fn new(key: KeyCode, action: GameAction) -> Self {
+        Self {
+            modifier: None,
+            key,
+            action,
+        }
+    }
+
+    const fn with_modifier(modifier: KeyCode, key: KeyCode, action: GameAction) -> Self {
+        Self {
+            modifier: Some(modifier),
+            key,
+            action,
+        }
+    }
+
+    pub fn just_pressed(&self, keyboard_input: &Input<KeyCode>) -> bool {
+        keyboard_input.just_pressed(self.key) && self.modifier_pressed(keyboard_input)
+    }
+
+    pub fn pressed(&self, keyboard_input: &Input<KeyCode>) -> bool {
+        keyboard_input.pressed(self.key) && self.modifier_pressed(keyboard_input)
+    }
+
+    /// The modifier must be held on either side of the keyboard. Without one, no modifier may be held, so that
+    /// Shift+1 doesn't trigger the binding of 1 too
+    fn modifier_pressed(&self, keyboard_input: &Input<KeyCode>) -> bool {
+        match self.modifier {
+            Some(modifier) => MODIFIERS.into_iter().any(|key| {
+                left_modifier(key) == left_modifier(modifier) && keyboard_input.pressed(key)
+            }),
+            None => !keyboard_input.any_pressed(MODIFIERS),
+        }
+    }
+
+    /// True if both bindings are triggered by the same keys
+    pub fn same_keys(&self, other: &KeyBinding) -> bool {
+        self.modifier.map(left_modifier) == other.modifier.map(left_modifier)
+            && self.key == other.key
+    }
+
+    /// Name of the keys, like "Alt+Return"
+    pub fn keys_name(&self) -> String {
+        match self.modifier {
+            Some(modifier) => format!("{}+{}", key_name(modifier), key_name(self.key)),
+            None => key_name(self.key),
+        }
+    }
+}
+
+/// Left side key of a modifier, the key itself for the others
+fn left_modifier(key: KeyCode) -> KeyCode {
+    match key {
+        KeyCode::ControlRight => KeyCode::ControlLeft,
+        KeyCode::ShiftRight => KeyCode::ShiftLeft,
+        KeyCode::AltRight => KeyCode::AltLeft,
+        _ => key,
+    }
+}
+
+fn key_name(key: KeyCode) -> String {
+    match key {
+        KeyCode::ControlLeft | KeyCode::ControlRight => "Ctrl".to_string(),
+        KeyCode::ShiftLeft | KeyCode::ShiftRight => "Shift".to_string(),
+        KeyCode::AltLeft | KeyCode::AltRight => "Alt".to_string(),
+        _ => {
+            let name = format!("{key:?}");
+            // Number keys are `Key1`, `Key2`...
+            match name.strip_prefix("Key") {
+                Some(number) => number.to_string(),
+                None => name,
             }
-            GameControl::Left => {
-                keyboard_input.pressed(KeyCode::A) || keyboard_input.pressed(KeyCode::Left)
+        }
+    }
+}
+
+/// Keyboard bindings of the actions. Part of the settings, changed in the Controls page.
+#[derive(Resource, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct KeyBindings(Vec<KeyBinding>);
+
+/// Default keys of the first barrels, and with Shift of the tray items
+const NUMBER_KEYS: [KeyCode; 9] = [
+    KeyCode::Key1,
+    KeyCode::Key2,
+    KeyCode::Key3,
+    KeyCode::Key4,
+    KeyCode::Key5,
+    KeyCode::Key6,
+    KeyCode::Key7,
+    KeyCode::Key8,
+    KeyCode::Key9,
+];
+/// Default keys of the first seats from the left, under the right hand
+const SEAT_KEYS: [KeyCode; 4] = [KeyCode::J, KeyCode::K, KeyCode::L, KeyCode::Semicolon];
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        let mut bindings = vec![
+            KeyBinding::new(KeyCode::W, GameAction::ShelfUp),
+            KeyBinding::new(KeyCode::Up, GameAction::ShelfUp),
+            KeyBinding::new(KeyCode::S, GameAction::ShelfDown),
+            KeyBinding::new(KeyCode::Down, GameAction::ShelfDown),
+            // The arrows also move the focus, like the D-pad
+            KeyBinding::new(KeyCode::Up, GameAction::NavigateUp),
+            KeyBinding::new(KeyCode::Down, GameAction::NavigateDown),
+            KeyBinding::new(KeyCode::Left, GameAction::NavigateLeft),
+            KeyBinding::new(KeyCode::Right, GameAction::NavigateRight),
+            KeyBinding::new(KeyCode::Return, GameAction::Activate),
+            // Escape pauses the game and backs out of every screen
+            KeyBinding::new(KeyCode::Escape, GameAction::Pause),
+            KeyBinding::new(KeyCode::Escape, GameAction::Back),
+            KeyBinding::new(KeyCode::Space, GameAction::Confirm),
+            KeyBinding::new(KeyCode::F, GameAction::ToggleFullscreen),
+            KeyBinding::with_modifier(
+                KeyCode::AltLeft,
+                KeyCode::Return,
+                GameAction::ToggleFullscreen,
+            ),
+            KeyBinding::new(KeyCode::Tab, GameAction::NextTrayItem),
+            KeyBinding::new(KeyCode::Back, GameAction::DumpTray),
+        ];
+        // The barrels on the number keys, and the tray items on Shift and the number keys
+        bindings.extend(
+            NUMBER_KEYS
+                .into_iter()
+                .enumerate()
+                .map(|(index, key)| KeyBinding::new(key, GameAction::SelectBarrel(index))),
+        );
+        bindings.extend(NUMBER_KEYS.into_iter().enumerate().map(|(index, key)| {
+            KeyBinding::with_modifier(KeyCode::ShiftLeft, key, GameAction::SelectTrayItem(index))
+        }));
+        bindings.extend(
+            SEAT_KEYS
+                .into_iter()
+                .enumerate()
+                .map(|(index, key)| KeyBinding::new(key, GameAction::ServeSeat(index))),
+        );
+        Self(bindings)
+    }
+}
+
+impl KeyBindings {
+    pub fn iter(&self) -> impl Iterator<Item = &KeyBinding> {
+        self.0.iter()
+    }
+
+    /// The `slot`th binding of `action`
+    pub fn get(&self, action: GameAction, slot: usize) -> Option<&KeyBinding> {
+        self.0
+            .iter()
+            .filter(|binding| binding.action == action)
+            .nth(slot)
+    }
+
+    /// Replaces the `slot`th binding of `action`, or adds it if the action has fewer bindings
+    pub fn set(
+        &mut self,
+        action: GameAction,
+        slot: usize,
+        modifier: Option<KeyCode>,
+        key: KeyCode,
+    ) {
+        let binding = KeyBinding {
+            modifier,
+            key,
+            action,
+        };
+        match self
+            .0
+            .iter_mut()
+            .filter(|binding| binding.action == action)
+            .nth(slot)
+        {
+            Some(old_binding) => *old_binding = binding,
+            None => self.0.push(binding),
+        }
+    }
+
+    /// Moves the tray items still on their number key, their default before the barrels took the number keys, to
+    /// Shift and the same key
+    pub fn move_tray_items_to_shift(&mut self) {
+        for binding in &mut self.0 {
+            if let GameAction::SelectTrayItem(index) = binding.action {
+                if binding.modifier.is_none() && NUMBER_KEYS.get(index) == Some(&binding.key) {
+                    binding.modifier = Some(KeyCode::ShiftLeft);
+                }
             }
-            GameControl::Right => {
-                keyboard_input.pressed(KeyCode::D) || keyboard_input.pressed(KeyCode::Right)
+        }
+    }
+
+    /// Gives the default bindings to the actions without any, like the ones added after the bindings were saved.
+    /// Default keys already used by another action are skipped, the action stays unbound until it is rebound.
+    pub fn add_missing_actions(&mut self) {
+        let bound_actions: Vec<GameAction> = self.0.iter().map(|binding| binding.action).collect();
+        for default_binding in KeyBindings::default().0 {
+            if !bound_actions.contains(&default_binding.action)
+                && self.conflicts(&default_binding).next().is_none()
+            {
+                self.0.push(default_binding);
             }
         }
     }
+
+    /// Bindings of other actions triggered by the same keys, unless the actions share their keys on purpose
+    pub fn conflicts<'a>(
+        &'a self,
+        binding: &'a KeyBinding,
+    ) -> impl Iterator<Item = &'a KeyBinding> + 'a {
+        self.0.iter().filter(move |other| {
+            other.same_keys(binding)
+                && other.action != binding.action
+                && !other.action.shares_keys_with(binding.action)
+        })
+    }
+}
+
+/// Set while the Controls page waits for the keys of a binding. The keyboard triggers no actions meanwhile.
+#[derive(Resource, Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyCapture {
+    #[default]
+    Off,
+    Waiting,
+    /// Escape was pressed
+    Cancelled,
+    Captured {
+        modifier: Option<KeyCode>,
+        key: KeyCode,
+    },
 }
 
-pub fn get_movement(control: GameControl, input: &Res<Input<KeyCode>>) -> f32 {
-    if control.pressed(input) {
-        1.0
-    } else {
-        0.0
+impl KeyCapture {
+    /// Moves from `Waiting` to `Captured` (or `Cancelled`) on the first key press that isn't a modifier
+    pub fn capture(&mut self, keyboard_input: &Input<KeyCode>) {
+        if *self != KeyCapture::Waiting {
+            return;
+        }
+        let Some(key) = keyboard_input
+            .get_just_pressed()
+            .find(|key| !MODIFIERS.contains(key))
+        else {
+            return;
+        };
+        *self = if *key == KeyCode::Escape {
+            KeyCapture::Cancelled
+        } else {
+            KeyCapture::Captured {
+                modifier: MODIFIERS
+                    .into_iter()
+                    .find(|modifier| keyboard_input.pressed(*modifier)),
+                key: *key,
+            }
+        };
     }
 }
+
+/// Gamepad buttons and the action they trigger, on every connected gamepad
+pub const GAMEPAD_BINDINGS: [(GamepadButtonType, GameAction); 16] = [
+    // The D-pad moves the focus, and the camera between the shelves
+    (GamepadButtonType::DPadUp, GameAction::ShelfUp),
+    (GamepadButtonType::DPadUp, GameAction::NavigateUp),
+    (GamepadButtonType::DPadDown, GameAction::ShelfDown),
+    (GamepadButtonType::DPadDown, GameAction::NavigateDown),
+    (GamepadButtonType::DPadLeft, GameAction::NavigateLeft),
+    (GamepadButtonType::DPadRight, GameAction::NavigateRight),
+    (GamepadButtonType::Start, GameAction::Pause),
+    // Continues the save game from the main menu, or opens the game mode selection without one
+    (GamepadButtonType::Start, GameAction::Confirm),
+    // Uses the focused target, and keeps the pour going while held. Never a click under the hidden mouse cursor
+    (GamepadButtonType::South, GameAction::Activate),
+    (GamepadButtonType::East, GameAction::Back),
+    // The first barrels of the catalogue, on the buttons East (Back) leaves free
+    (GamepadButtonType::West, GameAction::SelectBarrel(0)),
+    (GamepadButtonType::North, GameAction::SelectBarrel(1)),
+    (GamepadButtonType::LeftTrigger, GameAction::SelectBarrel(2)),
+    (GamepadButtonType::RightTrigger, GameAction::NextTrayItem),
+    (GamepadButtonType::LeftTrigger2, GameAction::DumpTray),
+    (GamepadButtonType::Select, GameAction::ToggleFullscreen),
+];
@@ -1,61 +1,310 @@
-use bevy::math::Vec3Swizzles;
+//! Input actions.
+//!
+//! Keyboard, mouse, touch and gamepad input is turned into [`GameAction`]s at the start of every frame, before
+//! `Update`. Game systems read the [`Actions`] resource instead of the input devices, so every input source
+//! behaves the same. The pointer (mouse cursor or touch) is tracked apart, for the systems that hit-test what
+//! is under it. A touch interacts once released, if it was a short tap, and touch swipes switch the shelves like
+//! the mouse wheel. The keyboard bindings are a setting (see [`KeyBindings`]), rebound in the Controls page.
+//! Systems acting on the actions before `Update` run after [`ActionsSystem`].
+
+use bevy::input::mouse::MouseWheel;
+use bevy::input::InputSystem;
 use bevy::prelude::*;
+use bevy::utils::HashMap;
+use bevy::window::PrimaryWindow;
+use serde::{Deserialize, Serialize};
 
-use crate::actions::game_control::{get_movement, GameControl};
+use crate::actions::game_control::GAMEPAD_BINDINGS;
 
 mod game_control;
 
-pub const FOLLOW_EPSILON: f32 = 5.;
+pub use self::game_control::{KeyBindings, KeyCapture};
+
+/// Vertical distance, in logical pixels, a touch must travel to be a swipe
+const SWIPE_MIN_DISTANCE: f32 = 100.;
+/// Touches held longer are not taps, like the ones held to pour
+const TAP_MAX_DURATION: f32 = 0.3;
 
 pub struct ActionsPlugin;
 
-// This plugin listens for keyboard input and converts the input into Actions
+// This plugin listens for the input devices and converts their input into Actions
 // Actions can then be used as a resource in other systems to act on the player input.
 impl Plugin for ActionsPlugin {
     fn build(&self, app: &mut App) {
         app //
             .init_resource::<Actions>()
-            // .add_systems(
-            //     Update,
-            //     set_movement_actions.run_if(in_state(GameState::Playing)),
-            // )
-            ;
+            .init_resource::<KeyCapture>()
+            .add_systems(
+                PreUpdate,
+                (
+                    clear_actions,
+                    keyboard_actions,
+                    mouse_actions,
+                    touch_actions,
+                    gamepad_actions,
+                )
+                    .chain()
+                    .in_set(ActionsSystem)
+                    .after(InputSystem),
+            );
     }
 }
 
+/// Label of the systems filling [`Actions`]
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ActionsSystem;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum GameAction {
+    /// Moves the camera one shelf up
+    ShelfUp,
+    /// Moves the camera one shelf down
+    ShelfDown,
+    /// Pauses or resumes the game
+    Pause,
+    /// Starts a game from the main menu
+    Confirm,
+    /// Leaves the current screen
+    Back,
+    ToggleFullscreen,
+    /// Uses the interactible under the pointer. Held, keeps the pour going
+    Interact,
+    /// Moves the focus to the button above
+    NavigateUp,
+    NavigateDown,
+    /// Moves the focus to the button on the left, or to the interactible on the left in game
+    NavigateLeft,
+    NavigateRight,
+    /// Presses the focused button, or uses the focused interactible
+    Activate,
+    /// Uses the barrel at this index in the drinks catalogue
+    SelectBarrel(usize),
+    /// Uses the customer sitting at the bar slot of this index, from left to right
+    ServeSeat(usize),
+    /// Selects the tray item at this index
+    SelectTrayItem(usize),
+    NextTrayItem,
+    DumpTray,
+}
+
+impl GameAction {
+    /// Actions listed in the Controls page, in order, with a barrel action for each of the `barrel_count` barrels
+    /// and a seat action for each of the `seat_count` seats
+    pub fn rebindable(barrel_count: usize, seat_count: usize) -> Vec<GameAction> {
+        let mut actions = vec![
+            GameAction::ShelfUp,
+            GameAction::ShelfDown,
+            GameAction::NavigateUp,
+            GameAction::NavigateDown,
+            GameAction::NavigateLeft,
+            GameAction::NavigateRight,
+            GameAction::Activate,
+            GameAction::Pause,
+            GameAction::Back,
+            GameAction::Confirm,
+            GameAction::ToggleFullscreen,
+        ];
+        actions.extend((0..barrel_count).map(GameAction::SelectBarrel));
+        actions.extend((0..seat_count).map(GameAction::ServeSeat));
+        actions.extend([GameAction::NextTrayItem, GameAction::DumpTray]);
+        actions.extend((0..9).map(GameAction::SelectTrayItem));
+        actions
+    }
+
+    pub fn label(&self) -> String {
+        match self {
+            GameAction::ShelfUp => "Shelf up".to_string(),
+            GameAction::ShelfDown => "Shelf down".to_string(),
+            GameAction::Pause => "Pause".to_string(),
+            GameAction::Confirm => "Play".to_string(),
+            GameAction::Back => "Back".to_string(),
+            GameAction::ToggleFullscreen => "Fullscreen".to_string(),
+            GameAction::Interact => "Interact".to_string(),
+            GameAction::NavigateUp => "Focus up".to_string(),
+            GameAction::NavigateDown => "Focus down".to_string(),
+            GameAction::NavigateLeft => "Focus left".to_string(),
+            GameAction::NavigateRight => "Focus right".to_string(),
+            GameAction::Activate => "Use focused".to_string(),
+            GameAction::SelectBarrel(index) => format!("Barrel {}", index + 1),
+            GameAction::ServeSeat(index) => format!("Seat {}", index + 1),
+            GameAction::SelectTrayItem(index) => format!("Tray item {}", index + 1),
+            GameAction::NextTrayItem => "Next tray item".to_string(),
+            GameAction::DumpTray => "Dump tray".to_string(),
+        }
+    }
+
+    /// Actions meant to be bound to the same keys, like Escape pausing the game and leaving the menus
+    fn shares_keys_with(&self, other: GameAction) -> bool {
+        matches!(
+            (self, other),
+            (GameAction::Pause, GameAction::Back)
+                | (GameAction::Back, GameAction::Pause)
+                | (GameAction::ShelfUp, GameAction::NavigateUp)
+                | (GameAction::NavigateUp, GameAction::ShelfUp)
+                | (GameAction::ShelfDown, GameAction::NavigateDown)
+                | (GameAction::NavigateDown, GameAction::ShelfDown)
+        )
+    }
+}
+
+/// Actions triggered by the input of this frame.
 #[derive(Default, Resource)]
 pub struct Actions {
-    pub player_movement: Option<Vec2>,
+    just_pressed: Vec<GameAction>,
+    pressed: Vec<GameAction>,
+    /// Viewport position of the mouse cursor, or of the touch
+    pointer: Option<Vec2>,
 }
 
-pub fn set_movement_actions(
+impl Actions {
+    /// True on the frame the action is triggered
+    pub fn just_pressed(&self, action: GameAction) -> bool {
+        self.just_pressed.contains(&action)
+    }
+
+    /// True while the input triggering the action is held
+    pub fn pressed(&self, action: GameAction) -> bool {
+        self.pressed.contains(&action)
+    }
+
+    /// Like [`Actions::just_pressed`], but the systems running afterwards in this frame don't see the action
+    pub fn consume(&mut self, action: GameAction) -> bool {
+        let just_pressed = self.just_pressed(action);
+        self.just_pressed.retain(|other| *other != action);
+        just_pressed
+    }
+
+    /// Every action triggered in this frame
+    pub fn iter_just_pressed(&self) -> impl Iterator<Item = GameAction> + '_ {
+        self.just_pressed.iter().copied()
+    }
+
+    pub fn pointer(&self) -> Option<Vec2> {
+        self.pointer
+    }
+
+    fn press(&mut self, action: GameAction) {
+        if !self.just_pressed(action) {
+            self.just_pressed.push(action);
+        }
+    }
+
+    fn hold(&mut self, action: GameAction) {
+        if !self.pressed(action) {
+            self.pressed.push(action);
+        }
+    }
+}
+
+fn clear_actions(mut actions: ResMut<Actions>) {
+    actions.just_pressed.clear();
+    actions.pressed.clear();
+    actions.pointer = None;
+}
+
+fn keyboard_actions(
     mut actions: ResMut<Actions>,
     keyboard_input: Res<Input<KeyCode>>,
-    touch_input: Res<Touches>,
-    player: Query<&Transform, With<Player>>,
-    camera: Query<(&Camera, &GlobalTransform), With<Camera2d>>,
+    key_bindings: Res<KeyBindings>,
+    mut key_capture: ResMut<KeyCapture>,
 ) {
-    let mut player_movement = Vec2::new(
-        get_movement(GameControl::Right, &keyboard_input)
-            - get_movement(GameControl::Left, &keyboard_input),
-        get_movement(GameControl::Up, &keyboard_input)
-            - get_movement(GameControl::Down, &keyboard_input),
-    );
-
-    if let Some(touch_position) = touch_input.first_pressed_position() {
-        let (camera, camera_transform) = camera.single();
-        if let Some(touch_position) = camera.viewport_to_world_2d(camera_transform, touch_position)
-        {
-            let diff = touch_position - player.single().translation.xy();
-            if diff.length() > FOLLOW_EPSILON {
-                player_movement = diff.normalize();
-            }
+    if *key_capture != KeyCapture::Off {
+        key_capture.capture(&keyboard_input);
+        return;
+    }
+
+    for binding in key_bindings.iter() {
+        if binding.just_pressed(&keyboard_input) {
+            actions.press(binding.action);
+        }
+        if binding.pressed(&keyboard_input) {
+            actions.hold(binding.action);
         }
     }
+}
+
+fn mouse_actions(
+    mut actions: ResMut<Actions>,
+    mouse_buttons: Res<Input<MouseButton>>,
+    mut scroll_evr: EventReader<MouseWheel>,
+    windows_q: Query<&Window, With<PrimaryWindow>>,
+) {
+    if mouse_buttons.just_pressed(MouseButton::Left) {
+        actions.press(GameAction::Interact);
+    }
+    if mouse_buttons.pressed(MouseButton::Left) {
+        actions.hold(GameAction::Interact);
+    }
 
-    if player_movement != Vec2::ZERO {
-        actions.player_movement = Some(player_movement.normalize());
-    } else {
-        actions.player_movement = None;
+    if let Some(scroll) = scroll_evr.read().last() {
+        if scroll.y < 0. {
+            actions.press(GameAction::ShelfDown);
+        } else if scroll.y > 0. {
+            actions.press(GameAction::ShelfUp);
+        }
+    }
+
+    if let Ok(window) = windows_q.get_single() {
+        actions.pointer = window.cursor_position();
+    }
+}
+
+/// Short touches that barely move are taps, released as `GameAction::Interact`. `touch_starts` holds the time every
+/// touch started at
+fn touch_actions(
+    mut actions: ResMut<Actions>,
+    touches: Res<Touches>,
+    time: Res<Time>,
+    mut touch_starts: Local<HashMap<u64, f32>>,
+) {
+    for touch in touches.iter_just_pressed() {
+        touch_starts.insert(touch.id(), time.elapsed_seconds());
+    }
+    if let Some(touch) = touches.iter().next() {
+        actions.hold(GameAction::Interact);
+        actions.pointer = Some(touch.position());
+    }
+
+    for touch in touches.iter_just_canceled() {
+        touch_starts.remove(&touch.id());
+    }
+
+    for touch in touches.iter_just_released() {
+        let distance = touch.distance();
+        let duration = touch_starts
+            .remove(&touch.id())
+            .map_or(f32::MAX, |start| time.elapsed_seconds() - start);
+        if distance.length() < SWIPE_MIN_DISTANCE && duration <= TAP_MAX_DURATION {
+            actions.press(GameAction::Interact);
+            actions.pointer = Some(touch.position());
+            continue;
+        }
+        // Mostly vertical swipes. Like the mouse wheel, swiping up scrolls down
+        if distance.y.abs() < SWIPE_MIN_DISTANCE || distance.y.abs() < distance.x.abs() * 2. {
+            continue;
+        }
+        if distance.y < 0. {
+            actions.press(GameAction::ShelfDown);
+        } else {
+            actions.press(GameAction::ShelfUp);
+        }
+    }
+}
+
+fn gamepad_actions(
+    mut actions: ResMut<Actions>,
+    gamepads: Res<Gamepads>,
+    gamepad_buttons: Res<Input<GamepadButton>>,
+) {
+    for gamepad in gamepads.iter() {
+        for (button_type, action) in GAMEPAD_BINDINGS {
+            let button = GamepadButton::new(gamepad, button_type);
+            if gamepad_buttons.just_pressed(button) {
+                actions.press(action);
+            }
+            if gamepad_buttons.pressed(button) {
+                actions.hold(action);
+            }
+        }
     }
 }
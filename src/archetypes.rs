@@ -0,0 +1,275 @@
+//! Customer archetypes.
+//!
+//! The kinds of customers coming to the tavern (regulars, travelers, nobles...) are defined in
+//! `assets/data/archetypes.customers.ron`, loaded as an [`ArchetypeCatalogue`] asset by the `LoadingPlugin` and
+//! then copied to the [`Archetypes`] resource. Each archetype has its own texture, patience, tip and drink
+//! preferences. Drinks are referenced by their name in the drink catalogue.
+
+use std::collections::HashMap;
+
+use bevy::asset::{io::Reader, AssetLoader, AsyncReadExt, LoadContext};
+use bevy::prelude::*;
+use bevy::utils::intern::{Interned, Interner};
+use bevy::utils::BoxedFuture;
+use rand::seq::SliceRandom;
+use rand::Rng;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use thiserror::Error;
+
+use crate::drinks::{Drink, DrinkCatalogue};
+use crate::loading::{ArchetypeAssets, DrinkAssets};
+
+/// Names of the archetypes, interned so that [`Archetype`] is `Copy`
+static ARCHETYPE_NAMES: Interner<str> = Interner::new();
+
+/// Identifies an archetype by its name in the catalogue, so that it stays the same when archetypes are added or
+/// reordered. Saved as the name.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Archetype(Interned<str>);
+
+impl Archetype {
+    fn named(name: &str) -> Self {
+        Archetype(ARCHETYPE_NAMES.intern(name))
+    }
+}
+
+impl Serialize for Archetype {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.0)
+    }
+}
+
+impl<'de> Deserialize<'de> for Archetype {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer).map(|name| Archetype::named(&name))
+    }
+}
+
+#[derive(Clone)]
+pub struct ArchetypeDefinition {
+    /// How often the archetype comes in, relative to the other ones
+    pub weight: u32,
+    pub texture: Handle<Image>,
+    /// Multiplies `CustomersStats.customers_wait_duration`
+    pub patience: f32,
+    /// Multiplies the tip paid on top of the drinks
+    pub tip: f64,
+    /// How often each drink is ordered, relative to the other ones. Drinks not listed have a weight of 1
+    drink_weights: HashMap<Drink, u32>,
+}
+
+impl ArchetypeDefinition {
+    pub fn drink_weight(&self, drink: Drink) -> u32 {
+        self.drink_weights.get(&drink).copied().unwrap_or(1)
+    }
+
+    /// Picks one of `drinks` following the preferences of the archetype.
+    /// Falls back to a uniform choice if the archetype likes none of them.
+    pub fn choose_drink<R: Rng>(&self, drinks: &[Drink], rng: &mut R) -> Option<Drink> {
+        match drinks.choose_weighted(rng, |drink| self.drink_weight(*drink)) {
+            Ok(drink) => Some(*drink),
+            // Every weight is 0, or there are no drinks
+            Err(_) => drinks.choose(rng).copied(),
+        }
+    }
+}
+
+/// Every archetype of the catalogue, available once the assets are loaded.
+#[derive(Resource)]
+pub struct Archetypes(Vec<(Archetype, ArchetypeDefinition)>);
+
+impl Archetypes {
+    pub fn get(&self, archetype: Archetype) -> &ArchetypeDefinition {
+        self.0
+            .iter()
+            .find(|(other, _)| *other == archetype)
+            .map(|(_, definition)| definition)
+            .expect("Archetype should be in the catalogue")
+    }
+
+    pub fn contains(&self, archetype: Archetype) -> bool {
+        self.0.iter().any(|(other, _)| *other == archetype)
+    }
+
+    /// Picks an archetype following their weights
+    pub fn choose<R: Rng>(&self, rng: &mut R) -> Archetype {
+        self.0
+            .choose_weighted(rng, |(_, definition)| definition.weight)
+            .unwrap_or(&self.0[0])
+            .0
+    }
+}
+
+impl FromWorld for Archetypes {
+    fn from_world(world: &mut World) -> Self {
+        let drink_catalogue = world
+            .resource::<Assets<DrinkCatalogue>>()
+            .get(&world.resource::<DrinkAssets>().catalogue)
+            .expect("Drink catalogue should be loaded");
+        let archetype_catalogue = world
+            .resource::<Assets<ArchetypeCatalogue>>()
+            .get(&world.resource::<ArchetypeAssets>().catalogue)
+            .expect("Archetype catalogue should be loaded");
+
+        let archetypes = archetype_catalogue
+            .archetypes
+            .iter()
+            .map(|loaded| {
+                let definition = ArchetypeDefinition {
+                    weight: loaded.weight,
+                    texture: loaded.texture.clone(),
+                    patience: loaded.patience,
+                    tip: loaded.tip,
+                    drink_weights: loaded
+                        .drinks
+                        .iter()
+                        .filter_map(|(drink_name, weight)| {
+                            let drink = drink_catalogue.find(drink_name);
+                            if drink.is_none() {
+                                warn!(
+                                    "Archetype {} prefers unknown drink {drink_name}",
+                                    loaded.name
+                                );
+                            }
+                            drink.map(|drink| (drink, *weight))
+                        })
+                        .collect(),
+                };
+                (Archetype::named(&loaded.name), definition)
+            })
+            .collect::<Vec<_>>();
+        assert!(
+            !archetypes.is_empty(),
+            "Archetype catalogue should have at least one archetype"
+        );
+
+        Archetypes(archetypes)
+    }
+}
+
+#[derive(Asset, TypePath)]
+pub struct ArchetypeCatalogue {
+    archetypes: Vec<LoadedArchetype>,
+}
+
+/// An archetype with its texture loaded but its drinks not yet matched with the drink catalogue
+struct LoadedArchetype {
+    name: String,
+    weight: u32,
+    texture: Handle<Image>,
+    patience: f32,
+    tip: f64,
+    drinks: HashMap<String, u32>,
+}
+
+/// Format of the catalogue file
+#[derive(Deserialize)]
+struct ArchetypeCatalogueFile {
+    archetypes: Vec<ArchetypeFile>,
+}
+
+#[derive(Deserialize)]
+struct ArchetypeFile {
+    name: String,
+    #[serde(default = "default_one")]
+    weight: u32,
+    /// Asset path of the customer texture
+    texture: String,
+    #[serde(default = "default_one")]
+    patience: f32,
+    #[serde(default = "default_one")]
+    tip: f64,
+    /// Drink name to weight
+    #[serde(default)]
+    drinks: HashMap<String, u32>,
+}
+
+fn default_one<T: From<u8>>() -> T {
+    T::from(1)
+}
+
+#[derive(Default)]
+pub struct ArchetypeCatalogueLoader;
+
+#[derive(Debug, Error)]
+pub enum ArchetypeCatalogueLoaderError {
+    #[error("could not read archetype catalogue: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("could not parse archetype catalogue: {0}")]
+    Ron(#[from] ron::error::SpannedError),
+}
+
+impl AssetLoader for ArchetypeCatalogueLoader {
+    type Asset = ArchetypeCatalogue;
+    type Settings = ();
+    type Error = ArchetypeCatalogueLoaderError;
+
+    fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader,
+        _settings: &'a Self::Settings,
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<Self::Asset, Self::Error>> {
+        Box::pin(async move {
+            let mut bytes = Vec::new();
+            reader.read_to_end(&mut bytes).await?;
+            let catalogue_file: ArchetypeCatalogueFile = ron::de::from_bytes(&bytes)?;
+
+            let archetypes = catalogue_file
+                .archetypes
+                .into_iter()
+                .map(|archetype_file| LoadedArchetype {
+                    name: archetype_file.name,
+                    weight: archetype_file.weight,
+                    texture: load_context.load(archetype_file.texture),
+                    patience: archetype_file.patience,
+                    tip: archetype_file.tip,
+                    drinks: archetype_file.drinks,
+                })
+                .collect();
+
+            Ok(ArchetypeCatalogue { archetypes })
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["customers.ron"]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn archetypes(names: &[&str]) -> Archetypes {
+        Archetypes(
+            names
+                .iter()
+                .map(|name| {
+                    let definition = ArchetypeDefinition {
+                        weight: 1,
+                        texture: Handle::default(),
+                        patience: 1.,
+                        tip: 1.,
+                        drink_weights: HashMap::new(),
+                    };
+                    (Archetype::named(name), definition)
+                })
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn archetypes_are_saved_by_name() {
+        let traveler = archetypes(&["Regular", "Traveler"]).0[1].0;
+        let serialized = ron::to_string(&traveler).unwrap();
+        assert_eq!(serialized, "\"Traveler\"");
+
+        // Still the same archetype once the catalogue is reordered
+        let reordered = archetypes(&["Noble", "Traveler", "Regular"]);
+        let restored: Archetype = ron::from_str(&serialized).unwrap();
+        assert_eq!(restored, traveler);
+        assert!(reordered.contains(restored));
+        assert!(!archetypes(&["Regular"]).contains(restored));
+    }
+}
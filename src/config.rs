@@ -0,0 +1,232 @@
+//! Persistent user settings.
+//!
+//! The settings are read with [`crate::persistence`] when the plugin is built, so they are available
+//! before `GameState::Menu`, and are written again every time one of the setting resources changes.
+
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::actions::KeyBindings;
+use crate::persistence::{self, Location};
+use crate::{AdaptiveDifficulty, Difficulty, PourMode, ScreenMode};
+
+const SETTINGS_KEY: &str = "settings";
+/// Bump when the format of [`SettingsFile`] changes and add the migration to [`SettingsFile::migrate`].
+const SETTINGS_VERSION: u32 = 2;
+
+pub struct ConfigPlugin;
+
+impl Plugin for ConfigPlugin {
+    fn build(&self, app: &mut App) {
+        let settings_file = SettingsFile::load();
+
+        app //
+            .insert_resource(settings_file.screen_mode)
+            .insert_resource(settings_file.difficulty)
+            .insert_resource(settings_file.adaptive_difficulty)
+            .insert_resource(settings_file.pour_mode)
+            .insert_resource(settings_file.key_bindings)
+            .add_systems(Update, save_settings);
+    }
+}
+
+/// Stored settings. Fields missing in the file (added in newer versions) take their default value.
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+struct SettingsFile {
+    version: u32,
+    screen_mode: ScreenMode,
+    difficulty: Difficulty,
+    adaptive_difficulty: AdaptiveDifficulty,
+    pour_mode: PourMode,
+    key_bindings: KeyBindings,
+}
+
+impl Default for SettingsFile {
+    fn default() -> Self {
+        Self {
+            version: SETTINGS_VERSION,
+            screen_mode: ScreenMode::Windowed,
+            difficulty: Difficulty::default(),
+            adaptive_difficulty: AdaptiveDifficulty::default(),
+            pour_mode: PourMode::default(),
+            key_bindings: KeyBindings::default(),
+        }
+    }
+}
+
+impl SettingsFile {
+    fn load() -> Self {
+        match persistence::read::<SettingsFile>(Location::Config, SETTINGS_KEY) {
+            Ok(Some(settings_file)) => settings_file.migrate(),
+            Ok(None) => SettingsFile::default(),
+            Err(error) => {
+                warn!("Failed to load settings, using defaults: {error}");
+                SettingsFile::default()
+            }
+        }
+    }
+
+    /// Upgrades settings written by older versions of the game.
+    fn migrate(mut self) -> Self {
+        if self.version > SETTINGS_VERSION {
+            warn!(
+                "Settings version {} is newer than the supported {SETTINGS_VERSION}",
+                self.version
+            );
+        }
+        // Version 2 added the focus navigation and the barrel and seat hotkeys. The barrels take the number keys of
+        // the tray items
+        if self.version < 2 {
+            self.key_bindings.move_tray_items_to_shift();
+            self.key_bindings.add_missing_actions();
+        }
+        self.version = SETTINGS_VERSION;
+        self
+    }
+}
+
+/// Every resource that is stored in the settings file
+#[derive(SystemParam)]
+struct Settings<'w> {
+    screen_mode: Res<'w, ScreenMode>,
+    difficulty: Res<'w, Difficulty>,
+    adaptive_difficulty: Res<'w, AdaptiveDifficulty>,
+    pour_mode: Res<'w, PourMode>,
+    key_bindings: Res<'w, KeyBindings>,
+}
+
+impl Settings<'_> {
+    /// True if any setting changed after being loaded
+    fn changed(&self) -> bool {
+        changed_after_load(&self.screen_mode)
+            || changed_after_load(&self.difficulty)
+            || changed_after_load(&self.adaptive_difficulty)
+            || changed_after_load(&self.pour_mode)
+            || changed_after_load(&self.key_bindings)
+    }
+
+    fn to_file(&self) -> SettingsFile {
+        SettingsFile {
+            version: SETTINGS_VERSION,
+            screen_mode: *self.screen_mode,
+            difficulty: *self.difficulty,
+            adaptive_difficulty: *self.adaptive_difficulty,
+            pour_mode: *self.pour_mode,
+            key_bindings: self.key_bindings.clone(),
+        }
+    }
+}
+
+fn changed_after_load<T: Resource>(setting: &Res<T>) -> bool {
+    setting.is_changed() && !setting.is_added()
+}
+
+fn save_settings(settings: Settings) {
+    if settings.changed() {
+        if let Err(error) = persistence::write(Location::Config, SETTINGS_KEY, &settings.to_file())
+        {
+            warn!("Failed to save settings: {error}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::actions::GameAction;
+
+    use super::*;
+
+    #[test]
+    fn missing_settings_take_their_default() {
+        let settings_file: SettingsFile = ron::from_str("(version: 0, difficulty: Hard)").unwrap();
+        let settings_file = settings_file.migrate();
+        assert_eq!(settings_file.version, SETTINGS_VERSION);
+        assert_eq!(settings_file.screen_mode, ScreenMode::Windowed);
+        assert_eq!(settings_file.difficulty, Difficulty::Hard);
+        assert_eq!(settings_file.key_bindings, KeyBindings::default());
+    }
+
+    #[test]
+    fn migrate_keeps_the_current_settings() {
+        let settings_file = SettingsFile {
+            screen_mode: ScreenMode::BorderlessFullscreen,
+            ..Default::default()
+        };
+        let serialized = ron::to_string(&settings_file).unwrap();
+        let migrated = ron::from_str::<SettingsFile>(&serialized)
+            .unwrap()
+            .migrate();
+        assert_eq!(migrated.version, SETTINGS_VERSION);
+        assert_eq!(migrated.screen_mode, ScreenMode::BorderlessFullscreen);
+        assert_eq!(migrated.key_bindings, settings_file.key_bindings);
+    }
+
+    #[test]
+    fn migrate_binds_the_actions_added_since() {
+        let settings_file: SettingsFile = ron::from_str(
+            "(version: 1, key_bindings: ([(modifier: None, key: Space, action: Confirm)]))",
+        )
+        .unwrap();
+        let migrated = settings_file.migrate();
+        let bindings = &migrated.key_bindings;
+        assert_eq!(
+            bindings
+                .get(GameAction::Confirm, 0)
+                .map(|binding| binding.key),
+            Some(KeyCode::Space)
+        );
+        for action in [GameAction::NavigateLeft, GameAction::SelectBarrel(0)] {
+            assert_eq!(
+                bindings.get(action, 0),
+                KeyBindings::default().get(action, 0)
+            );
+        }
+        // Actions with several default keys get all of them
+        assert!(bindings.get(GameAction::ShelfUp, 1).is_some());
+    }
+
+    #[test]
+    fn migrate_skips_the_keys_already_in_use() {
+        let settings_file: SettingsFile = ron::from_str(
+            "(version: 1, key_bindings: ([(modifier: None, key: Key1, action: Confirm)]))",
+        )
+        .unwrap();
+        let migrated = settings_file.migrate();
+        let bindings = &migrated.key_bindings;
+        assert!(bindings.get(GameAction::SelectBarrel(0), 0).is_none());
+        assert_eq!(
+            bindings
+                .get(GameAction::SelectBarrel(1), 0)
+                .map(|binding| binding.key),
+            Some(KeyCode::Key2)
+        );
+        assert!(bindings.get(GameAction::Confirm, 1).is_none());
+    }
+
+    #[test]
+    fn migrate_moves_the_tray_items_off_the_barrel_keys() {
+        let settings_file: SettingsFile = ron::from_str(
+            "(version: 1, key_bindings: ([
+                (modifier: None, key: Key1, action: SelectTrayItem(0)),
+                (modifier: None, key: T, action: SelectTrayItem(1)),
+            ]))",
+        )
+        .unwrap();
+        let migrated = settings_file.migrate();
+        let bindings = &migrated.key_bindings;
+        let tray_item = bindings.get(GameAction::SelectTrayItem(0), 0).unwrap();
+        assert_eq!(tray_item.modifier, Some(KeyCode::ShiftLeft));
+        assert_eq!(tray_item.key, KeyCode::Key1);
+        // Rebound by the player, so kept as it is
+        let tray_item = bindings.get(GameAction::SelectTrayItem(1), 0).unwrap();
+        assert_eq!(tray_item.modifier, None);
+        assert_eq!(
+            bindings
+                .get(GameAction::SelectBarrel(0), 0)
+                .map(|binding| binding.key),
+            Some(KeyCode::Key1)
+        );
+    }
+}
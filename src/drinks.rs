@@ -0,0 +1,239 @@
+//! Drink catalogue.
+//!
+//! The drinks served in the tavern are defined in `assets/data/catalogue.drinks.ron`, loaded as a
+//! [`DrinkCatalogue`] asset by the `LoadingPlugin` and then copied to the [`Drinks`] resource.
+//! Adding a drink to the file is enough to get a new barrel and customers ordering it.
+
+use bevy::asset::{io::Reader, AssetLoader, AsyncReadExt, LoadContext};
+use bevy::prelude::*;
+use bevy::utils::intern::{Interned, Interner};
+use bevy::utils::BoxedFuture;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use thiserror::Error;
+
+use crate::loading::DrinkAssets;
+
+/// Names of the drinks, interned so that [`Drink`] is `Copy`
+static DRINK_NAMES: Interner<str> = Interner::new();
+
+/// Identifies a drink by its name in the catalogue, so that it stays the same when drinks are added or
+/// reordered. Saved as the name.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Drink(Interned<str>);
+
+impl Drink {
+    fn named(name: &str) -> Self {
+        Drink(DRINK_NAMES.intern(name))
+    }
+}
+
+impl Serialize for Drink {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.0)
+    }
+}
+
+impl<'de> Deserialize<'de> for Drink {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer).map(|name| Drink::named(&name))
+    }
+}
+
+#[derive(Clone)]
+pub struct DrinkDefinition {
+    pub name: String,
+    pub price: f64,
+    pub barrel_texture: Handle<Image>,
+    pub barrel_position: Vec3,
+    /// Reputation level needed for customers to order the drink and to use its barrel
+    pub unlock_reputation: u32,
+}
+
+impl DrinkDefinition {
+    pub fn is_unlocked(&self, reputation_level: u32) -> bool {
+        reputation_level >= self.unlock_reputation
+    }
+}
+
+/// Every drink of the catalogue, available once the assets are loaded.
+#[derive(Resource)]
+pub struct Drinks(Vec<(Drink, DrinkDefinition)>);
+
+impl Drinks {
+    fn new(definitions: Vec<DrinkDefinition>) -> Self {
+        Drinks(
+            definitions
+                .into_iter()
+                .map(|definition| (Drink::named(&definition.name), definition))
+                .collect(),
+        )
+    }
+
+    pub fn get(&self, drink: Drink) -> &DrinkDefinition {
+        self.iter()
+            .find(|(other, _)| *other == drink)
+            .map(|(_, definition)| definition)
+            .expect("Drink should be in the catalogue")
+    }
+
+    pub fn contains(&self, drink: Drink) -> bool {
+        self.iter().any(|(other, _)| other == drink)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (Drink, &DrinkDefinition)> {
+        self.0
+            .iter()
+            .map(|(drink, definition)| (*drink, definition))
+    }
+
+    /// The cheapest drink of the catalogue, if there is any
+    pub fn cheapest(&self) -> Option<Drink> {
+        self.iter()
+            .min_by(|(_, a), (_, b)| a.price.total_cmp(&b.price))
+            .map(|(drink, _)| drink)
+    }
+}
+
+#[cfg(test)]
+impl Drinks {
+    /// Catalogue of drinks with the given names and prices, unlocked from the start
+    pub fn from_prices(prices: &[(&str, f64)]) -> Self {
+        Drinks::new(
+            prices
+                .iter()
+                .map(|(name, price)| DrinkDefinition {
+                    name: name.to_string(),
+                    price: *price,
+                    barrel_texture: Handle::default(),
+                    barrel_position: Vec3::ZERO,
+                    unlock_reputation: 0,
+                })
+                .collect(),
+        )
+    }
+}
+
+impl FromWorld for Drinks {
+    fn from_world(world: &mut World) -> Self {
+        let drink_assets = world.resource::<DrinkAssets>();
+        let catalogue = world
+            .resource::<Assets<DrinkCatalogue>>()
+            .get(&drink_assets.catalogue)
+            .expect("Drink catalogue should be loaded");
+        Drinks::new(catalogue.drinks.clone())
+    }
+}
+
+#[derive(Asset, TypePath)]
+pub struct DrinkCatalogue {
+    drinks: Vec<DrinkDefinition>,
+}
+
+impl DrinkCatalogue {
+    /// Finds a drink by its name. The [`Drink`] is also valid for the [`Drinks`] resource.
+    pub fn find(&self, name: &str) -> Option<Drink> {
+        self.drinks
+            .iter()
+            .any(|definition| definition.name == name)
+            .then(|| Drink::named(name))
+    }
+}
+
+/// Format of the catalogue file
+#[derive(Deserialize)]
+struct DrinkCatalogueFile {
+    drinks: Vec<DrinkFile>,
+}
+
+#[derive(Deserialize)]
+struct DrinkFile {
+    name: String,
+    price: f64,
+    /// Asset path of the barrel texture
+    barrel_texture: String,
+    barrel_position: [f32; 3],
+    #[serde(default)]
+    unlock_reputation: u32,
+}
+
+#[derive(Default)]
+pub struct DrinkCatalogueLoader;
+
+#[derive(Debug, Error)]
+pub enum DrinkCatalogueLoaderError {
+    #[error("could not read drink catalogue: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("could not parse drink catalogue: {0}")]
+    Ron(#[from] ron::error::SpannedError),
+}
+
+impl AssetLoader for DrinkCatalogueLoader {
+    type Asset = DrinkCatalogue;
+    type Settings = ();
+    type Error = DrinkCatalogueLoaderError;
+
+    fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader,
+        _settings: &'a Self::Settings,
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<Self::Asset, Self::Error>> {
+        Box::pin(async move {
+            let mut bytes = Vec::new();
+            reader.read_to_end(&mut bytes).await?;
+            let catalogue_file: DrinkCatalogueFile = ron::de::from_bytes(&bytes)?;
+
+            let drinks = catalogue_file
+                .drinks
+                .into_iter()
+                .map(|drink_file| DrinkDefinition {
+                    name: drink_file.name,
+                    price: drink_file.price,
+                    barrel_texture: load_context.load(drink_file.barrel_texture),
+                    barrel_position: Vec3::from_array(drink_file.barrel_position),
+                    unlock_reputation: drink_file.unlock_reputation,
+                })
+                .collect();
+
+            Ok(DrinkCatalogue { drinks })
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["drinks.ron"]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn drinks_are_saved_by_name() {
+        let drinks = Drinks::from_prices(&[("Beer", 2.), ("Wine", 5.)]);
+        let (wine, _) = drinks.iter().nth(1).unwrap();
+        let serialized = ron::to_string(&wine).unwrap();
+        assert_eq!(serialized, "\"Wine\"");
+
+        // Still the same drink once the catalogue is reordered
+        let reordered = Drinks::from_prices(&[("Whiskey", 11.), ("Wine", 5.), ("Beer", 2.)]);
+        let restored: Drink = ron::from_str(&serialized).unwrap();
+        assert!(reordered.contains(restored));
+        assert_eq!(reordered.get(restored).price, 5.);
+    }
+
+    #[test]
+    fn unknown_drinks_are_not_in_the_catalogue() {
+        let drinks = Drinks::from_prices(&[("Beer", 2.)]);
+        let restored: Drink = ron::from_str("\"Mead\"").unwrap();
+        assert!(!drinks.contains(restored));
+    }
+
+    #[test]
+    fn cheapest_drink() {
+        let drinks = Drinks::from_prices(&[("Wine", 5.), ("Beer", 2.), ("Whiskey", 11.)]);
+        let (beer, _) = drinks.iter().nth(1).unwrap();
+        assert_eq!(drinks.cheapest(), Some(beer));
+        assert_eq!(Drinks::from_prices(&[]).cheapest(), None);
+    }
+}
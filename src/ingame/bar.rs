@@ -1,16 +1,35 @@
 use std::time::Duration;
 
+use bevy::ecs::system::SystemParam;
 use bevy::prelude::*;
-use rand::{seq::SliceRandom, Rng};
+use rand::{seq::IteratorRandom, Rng};
+use serde::{Deserialize, Serialize};
 
-use crate::{loading::TextureAssets, remove_value_from_vec, GameState, ScaleByAssetResolution};
-use std::fmt::{Display, Formatter};
+use crate::{
+    actions::{Actions, GameAction},
+    archetypes::Archetypes,
+    drinks::{Drink, Drinks},
+    loading::TextureAssets,
+    remove_value_from_vec, GameState, PourMode, ScaleByAssetResolution,
+};
 
 use super::{
     customer::{generate_random_customer, Customer, CustomerBundle},
-    ActiveInteractibleActions, ClickedInteractible, CustomersStats, DrinkInHand,
-    IgnoredInteractibleActions, IngameState, InteractibleAction, InteractibleBundle,
-    MainCameraIngame, MoveCameraTo, OnIngameScreen,
+    difficulty::Pacing,
+    glasses::{DirtyGlass, Glasses, Sink},
+    modifiers::Modifiers,
+    order::OrderConfig,
+    pour::{start_pour, PourConfig, PourQuality, PouredDrink, PouringGlass},
+    queue::{join_queue, QueueConfig, QueuedCustomer},
+    rng::{GameRng, RngStream},
+    save::start_session,
+    shop::{Upgrade, Upgrades},
+    stock::{spawn_barrel_gauge, Stock, StockConfig},
+    tray::{Tray, TrayConfig},
+    workday::tavern_is_open,
+    ActiveInteractibleActions, ClickedInteractible, CustomersStats, IgnoredInteractibleActions,
+    IngameState, InteractibleAction, InteractibleBundle, InteractibleFocus,
+    InteractionSpriteColors, MainCameraIngame, MoveCameraTo, OnIngameScreen, PlayerStats,
 };
 
 // The bar counter
@@ -21,94 +40,178 @@ pub struct BarPlugin;
 pub const BAR_CUSTOMER_TARGET_Y: f32 = -937.;
 /// The y position of the customer when they not visible
 pub const BAR_CUSTOMER_HIDDEN_Y: f32 = -1487.;
-const SLOT_LEFT_SPAWN_POINT: Vec3 = Vec3::new(-700., BAR_CUSTOMER_HIDDEN_Y, 3.); // z = 3.
-const SLOT_MIDDLE_SPAWN_POINT: Vec3 = Vec3::new(0., BAR_CUSTOMER_HIDDEN_Y, 2.);
-const SLOT_RIGHT_SPAWN_POINT: Vec3 = Vec3::new(700., BAR_CUSTOMER_HIDDEN_Y, 1.);
+const LOCKED_BARREL_COLOR: Color = Color::rgb(0.4, 0.4, 0.4);
 
 impl Plugin for BarPlugin {
     fn build(&self, app: &mut App) {
         app //
-            .add_systems(OnEnter(GameState::Playing), setup_bar)
+            .init_resource::<BarConfig>()
+            .add_systems(OnEnter(GameState::Playing), setup_bar.after(start_session))
             .add_systems(
                 Update,
                 (
                     handle_bar_interactible_click.run_if(in_state(IngameState::Running)),
+                    select_barrel.run_if(in_state(IngameState::Running)),
+                    serve_seat.run_if(in_state(IngameState::Running)),
+                    update_barrel_locks.run_if(in_state(IngameState::Running)),
                     spawn_customers_in_slots.run_if(in_state(IngameState::Running)),
-                    spawn_customer.run_if(in_state(IngameState::Running)),
+                    spawn_customer.run_if(in_state(IngameState::Running).and_then(tavern_is_open)),
+                    add_purchased_seats.run_if(resource_changed::<Modifiers>()),
                 ),
             );
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
-pub enum Drink {
-    Beer,
-    Wine,
-    Whiskey,
+/// Number and placement of the customer slots (seats) at the bar
+#[derive(Resource)]
+pub struct BarConfig {
+    /// Slots without upgrades
+    pub slot_count: usize,
+    /// x of the leftmost slot. The slots are evenly spaced up to `rightmost_slot_x`
+    pub leftmost_slot_x: f32,
+    pub rightmost_slot_x: f32,
 }
 
-impl Drink {
-    pub fn iterator() -> impl Iterator<Item = (Drink, Vec3)> {
-        vec![
-            (Drink::Beer, Vec3::new(750., -702., 14.)),
-            (Drink::Wine, Vec3::new(400., -702., 13.)),
-            (Drink::Whiskey, Vec3::new(50., -702., 12.)),
-        ]
-        .into_iter()
+impl BarConfig {
+    /// Slots once every seat upgrade is bought
+    pub fn max_slot_count(&self) -> usize {
+        self.slot_count + Upgrade::Seat.max_level() as usize
     }
+}
 
-    pub fn get_price(&self) -> f64 {
-        match self {
-            Drink::Beer => 2.,
-            Drink::Wine => 5.,
-            Drink::Whiskey => 11.,
+impl Default for BarConfig {
+    fn default() -> Self {
+        Self {
+            slot_count: 3,
+            leftmost_slot_x: -700.,
+            rightmost_slot_x: 700.,
         }
     }
 }
 
-impl Display for Drink {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        match self {
-            Drink::Beer => write!(f, "Beer"),
-            Drink::Wine => write!(f, "Wine"),
-            Drink::Whiskey => write!(f, "Whiskey"),
-        }
+impl BarConfig {
+    /// Spawn point of every one of `slot_count` slots, from left to right. The left ones are drawn on top of
+    /// the right ones
+    fn slot_spawn_points(&self, slot_count: usize) -> Vec<Vec3> {
+        let slot_count = slot_count.max(1);
+        let gap = if slot_count > 1 {
+            (self.rightmost_slot_x - self.leftmost_slot_x) / (slot_count - 1) as f32
+        } else {
+            0.
+        };
+        let first_x = if slot_count > 1 {
+            self.leftmost_slot_x
+        } else {
+            (self.leftmost_slot_x + self.rightmost_slot_x) / 2.
+        };
+
+        (0..slot_count)
+            .map(|index| {
+                Vec3::new(
+                    first_x + gap * index as f32,
+                    BAR_CUSTOMER_HIDDEN_Y,
+                    (slot_count - index) as f32,
+                )
+            })
+            .collect()
     }
 }
 
 #[derive(Component)]
 pub struct Bar {
     customer_slots: BarCustomerSlots,
-    // customer_queue: Vec<CustomerBundle>,
     customer_spawn_timer: Timer,
 }
 
 impl Bar {
+    fn new(bar_config: &BarConfig, slot_count: usize) -> Self {
+        Self {
+            customer_slots: BarCustomerSlots::new(bar_config, slot_count),
+            customer_spawn_timer: Timer::from_seconds(1., TimerMode::Once),
+        }
+    }
+
     pub fn remove_customer(&mut self, slot_marker: &CustomerSlotMarker) {
-        match slot_marker {
-            CustomerSlotMarker::Left => {
-                self.customer_slots.left.spawned = false;
-            }
-            CustomerSlotMarker::Middle => {
-                self.customer_slots.middle.spawned = false;
+        if let Some(slot) = self.customer_slots.get_mut(slot_marker) {
+            slot.spawned = false;
+        }
+    }
+
+    /// Adds empty slots up to `slot_count`, for the seats bought during the game. Every slot moves to its spot in
+    /// the new layout, the customers already seated stay where they are until they leave.
+    fn add_slots(&mut self, bar_config: &BarConfig, slot_count: usize) {
+        let slots = &mut self.customer_slots.0;
+        if slot_count <= slots.len() {
+            return;
+        }
+        for (index, spawn_point) in bar_config
+            .slot_spawn_points(slot_count)
+            .into_iter()
+            .enumerate()
+        {
+            match slots.get_mut(index) {
+                Some(slot) => slot.spawn_point = spawn_point,
+                None => slots.push(CustomerSlot::new(spawn_point)),
             }
-            CustomerSlotMarker::Right => {
-                self.customer_slots.right.spawned = false;
+        }
+    }
+
+    /// True if there are no customers at the bar or about to be spawned
+    pub fn is_empty(&self) -> bool {
+        self.customer_slots.is_empty()
+    }
+
+    /// Puts the customer in a random empty slot, or gives it back if every slot is taken
+    pub fn seat_customer(
+        &mut self,
+        customer: CustomerBundle,
+        game_rng: &mut GameRng,
+    ) -> Result<(), Box<CustomerBundle>> {
+        match self.customer_slots.get_random_empty_slot(game_rng) {
+            Some(slot) => {
+                slot.customer = Some(customer);
+                Ok(())
             }
+            None => Err(Box::new(customer)),
+        }
+    }
+
+    /// Marks the slot as taken by an already spawned customer (used when restoring a save).
+    /// Returns false if the bar has no such slot.
+    pub fn occupy_slot(&mut self, slot_marker: &CustomerSlotMarker) -> bool {
+        if let Some(slot) = self.customer_slots.get_mut(slot_marker) {
+            slot.spawned = true;
+            true
+        } else {
+            false
         }
     }
 }
 
-impl Default for Bar {
-    fn default() -> Self {
-        Self {
-            customer_slots: BarCustomerSlots::default(),
-            customer_spawn_timer: Timer::from_seconds(1., TimerMode::Once),
+#[derive(Component)]
+struct BarrelLabel(Drink);
+
+impl BarrelLabel {
+    fn text(&self, drinks: &Drinks, modifiers: &Modifiers, reputation_level: u32) -> String {
+        let drink_definition = drinks.get(self.0);
+        if modifiers.is_drink_unlocked(drinks, self.0, reputation_level) {
+            drink_definition.name.clone()
+        } else {
+            format!("Lv {}", drink_definition.unlock_reputation)
         }
     }
 }
 
-fn setup_bar(mut commands: Commands, textures: Res<TextureAssets>) {
+fn setup_bar(
+    mut commands: Commands,
+    textures: Res<TextureAssets>,
+    drinks: Res<Drinks>,
+    bar_config: Res<BarConfig>,
+    upgrades: Res<Upgrades>,
+) {
+    // `Modifiers` only catch up with the upgrades of the session on the next frame
+    let slot_count = Modifiers::from(&*upgrades).slot_count(bar_config.slot_count);
+
     // Bar counter
     commands
         .spawn(SpriteBundle {
@@ -120,15 +223,21 @@ fn setup_bar(mut commands: Commands, textures: Res<TextureAssets>) {
             },
             ..Default::default()
         })
-        .insert(Bar::default())
+        .insert(Bar::new(&bar_config, slot_count))
         .insert(InteractibleBundle::new(InteractibleAction::Bar))
         .insert(OnIngameScreen);
 
-    // Barrel Slots
-    for (drink, barrel_pos) in Drink::iterator() {
+    // Barrel Slots. Spawned locked, `update_barrel_locks` unlocks them
+    for (drink, drink_definition) in drinks.iter() {
+        let barrel_pos = drink_definition.barrel_position;
+        let mut interactible_bundle = InteractibleBundle::new(InteractibleAction::Barrel(drink));
+        interactible_bundle.set_colors(InteractionSpriteColors {
+            normal: LOCKED_BARREL_COLOR,
+            highlight: LOCKED_BARREL_COLOR,
+        });
         commands
             .spawn(SpriteBundle {
-                texture: textures.barrel.clone(),
+                texture: drink_definition.barrel_texture.clone(),
                 transform: Transform {
                     translation: barrel_pos,
                     scale: ScaleByAssetResolution::Res720p.scale(),
@@ -136,19 +245,16 @@ fn setup_bar(mut commands: Commands, textures: Res<TextureAssets>) {
                 },
                 ..Default::default()
             })
-            .insert(InteractibleBundle::new(InteractibleAction::Barrel(drink)))
+            .insert(interactible_bundle)
             .insert(OnIngameScreen);
+        spawn_barrel_gauge(&mut commands, drink, barrel_pos);
 
+        let barrel_label = BarrelLabel(drink);
         commands
             .spawn(Text2dBundle {
                 text: Text {
                     sections: vec![TextSection {
-                        value: match drink {
-                            Drink::Beer => "Beer",
-                            Drink::Wine => "Wine",
-                            Drink::Whiskey => "Whiskey",
-                        }
-                        .to_string(),
+                        value: String::new(),
                         style: TextStyle {
                             font_size: 55.,
                             color: Color::BLACK,
@@ -164,91 +270,287 @@ fn setup_bar(mut commands: Commands, textures: Res<TextureAssets>) {
                 },
                 ..Default::default()
             })
+            .insert(barrel_label)
             .insert(OnIngameScreen);
     }
 }
 
-#[derive(Default)]
+/// Clicks the barrel of `GameAction::SelectBarrel`, if it could be clicked with the mouse, and focuses it
+fn select_barrel(
+    mut commands: Commands,
+    actions: Res<Actions>,
+    drinks: Res<Drinks>,
+    barrels_q: Query<(Entity, &InteractibleAction)>,
+    active_interactibles_q: Query<&ActiveInteractibleActions>,
+    ignored_interactibles_q: Query<&IgnoredInteractibleActions>,
+    mut interactible_focus: ResMut<InteractibleFocus>,
+) {
+    let active_interactibles = active_interactibles_q.single();
+    let ignored_interactibles = ignored_interactibles_q.single();
+
+    for action in actions.iter_just_pressed() {
+        let GameAction::SelectBarrel(index) = action else {
+            continue;
+        };
+        let Some((drink, _)) = drinks.iter().nth(index) else {
+            continue;
+        };
+        let barrel = InteractibleAction::Barrel(drink);
+        if !active_interactibles.0.contains(&barrel) || ignored_interactibles.0.contains(&barrel) {
+            continue;
+        }
+        if let Some((entity, _)) = barrels_q
+            .iter()
+            .find(|(_, interactible_action)| **interactible_action == barrel)
+        {
+            commands.entity(entity).insert(ClickedInteractible);
+            interactible_focus.0 = Some(entity);
+        }
+    }
+}
+
+/// Clicks the customer at the bar slot of `GameAction::ServeSeat`, if it could be clicked with the mouse, and
+/// focuses it
+fn serve_seat(
+    mut commands: Commands,
+    actions: Res<Actions>,
+    customers_q: Query<(Entity, &CustomerSlotMarker), With<Customer>>,
+    active_interactibles_q: Query<&ActiveInteractibleActions>,
+    ignored_interactibles_q: Query<&IgnoredInteractibleActions>,
+    mut interactible_focus: ResMut<InteractibleFocus>,
+) {
+    let customer = InteractibleAction::Customer;
+    if !active_interactibles_q.single().0.contains(&customer)
+        || ignored_interactibles_q.single().0.contains(&customer)
+    {
+        return;
+    }
+
+    for action in actions.iter_just_pressed() {
+        let GameAction::ServeSeat(index) = action else {
+            continue;
+        };
+        if let Some((entity, _)) = customers_q
+            .iter()
+            .find(|(_, slot_marker)| slot_marker.0 == index)
+        {
+            commands.entity(entity).insert(ClickedInteractible);
+            interactible_focus.0 = Some(entity);
+        }
+    }
+}
+
+/// Activates the barrels of the unlocked drinks, either by reputation or by upgrades, and deactivates the rest
+fn update_barrel_locks(
+    mut barrels_q: Query<(&InteractibleAction, &mut InteractionSpriteColors)>,
+    mut barrel_labels_q: Query<(&mut Text, &BarrelLabel)>,
+    mut active_interactibles_q: Query<&mut ActiveInteractibleActions>,
+    player_stats: Res<PlayerStats>,
+    modifiers: Res<Modifiers>,
+    drinks: Res<Drinks>,
+) {
+    if !player_stats.is_changed() && !modifiers.is_changed() {
+        return;
+    }
+    let mut active_interactibles = active_interactibles_q.single_mut();
+
+    for (interactible_action, mut interaction_sprite_colors) in barrels_q.iter_mut() {
+        let InteractibleAction::Barrel(drink) = *interactible_action else {
+            continue;
+        };
+        let is_unlocked =
+            modifiers.is_drink_unlocked(&drinks, drink, player_stats.reputation_level);
+        let is_active = active_interactibles.0.contains(interactible_action);
+        if is_unlocked && !is_active {
+            active_interactibles.0.push(*interactible_action);
+            *interaction_sprite_colors = InteractionSpriteColors::default();
+        } else if !is_unlocked && is_active {
+            remove_value_from_vec(*interactible_action, &mut active_interactibles.0);
+            *interaction_sprite_colors = InteractionSpriteColors {
+                normal: LOCKED_BARREL_COLOR,
+                highlight: LOCKED_BARREL_COLOR,
+            };
+        }
+    }
+
+    for (mut text, barrel_label) in barrel_labels_q.iter_mut() {
+        text.sections[0].value =
+            barrel_label.text(&drinks, &modifiers, player_stats.reputation_level);
+    }
+}
+
 struct CustomerSlot {
     customer: Option<CustomerBundle>,
     spawned: bool,
+    spawn_point: Vec3,
 }
 
 impl CustomerSlot {
+    fn new(spawn_point: Vec3) -> Self {
+        Self {
+            customer: None,
+            spawned: false,
+            spawn_point,
+        }
+    }
+
     fn is_full(&self) -> bool {
         self.customer.is_some() || self.spawned
     }
 }
 
-#[derive(Component)]
-pub enum CustomerSlotMarker {
-    Left,
-    Middle,
-    Right,
-}
+/// Index of the slot of a customer, from left to right
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CustomerSlotMarker(pub usize);
 
-#[derive(Default)]
-struct BarCustomerSlots {
-    left: CustomerSlot,
-    middle: CustomerSlot,
-    right: CustomerSlot,
-}
+struct BarCustomerSlots(Vec<CustomerSlot>);
 
 impl BarCustomerSlots {
-    fn is_full(&self) -> bool {
-        self.left.is_full() && self.middle.is_full() && self.right.is_full()
+    fn new(bar_config: &BarConfig, slot_count: usize) -> Self {
+        Self(
+            bar_config
+                .slot_spawn_points(slot_count)
+                .into_iter()
+                .map(CustomerSlot::new)
+                .collect(),
+        )
     }
 
-    fn get_random_empty_slot(&mut self) -> Option<&mut CustomerSlot> {
-        let mut rng = rand::thread_rng();
-        let mut slots = vec![&mut self.left, &mut self.middle, &mut self.right];
-        slots.shuffle(&mut rng);
+    fn is_empty(&self) -> bool {
+        !self.0.iter().any(CustomerSlot::is_full)
+    }
 
-        slots.into_iter().find(|slot| !slot.is_full())
+    fn get_mut(&mut self, slot_marker: &CustomerSlotMarker) -> Option<&mut CustomerSlot> {
+        self.0.get_mut(slot_marker.0)
+    }
+
+    fn get_random_empty_slot(&mut self, game_rng: &mut GameRng) -> Option<&mut CustomerSlot> {
+        self.0
+            .iter_mut()
+            .filter(|slot| !slot.is_full())
+            .choose(game_rng.stream(RngStream::Seats))
     }
 }
 
+/// Brings a new customer in. It sits at the bar if there is a free slot and nobody is waiting in the queue,
+/// otherwise it joins the queue. Nobody comes in while the queue is full.
+#[allow(clippy::too_many_arguments)]
 fn spawn_customer(
+    mut commands: Commands,
     mut bar_q: Query<&mut Bar>,
-    textures: Res<TextureAssets>,
+    queued_customers_q: Query<&QueuedCustomer>,
     time: Res<Time>,
     customers_stats: Res<CustomersStats>,
+    queue_config: Res<QueueConfig>,
+    player_stats: Res<PlayerStats>,
+    modifiers: Res<Modifiers>,
+    drinks: Res<Drinks>,
+    archetypes: Res<Archetypes>,
+    order_config: Res<OrderConfig>,
+    pacing: Pacing,
+    mut game_rng: ResMut<GameRng>,
 ) {
     let mut bar = bar_q.single_mut();
+    let queue_length = queued_customers_q
+        .iter()
+        .filter(|queued_customer| queued_customer.is_waiting())
+        .count();
 
-    if !bar.customer_slots.is_full() && bar.customer_spawn_timer.tick(time.delta()).just_finished()
+    if queue_length < queue_config.max_length
+        && bar.customer_spawn_timer.tick(time.delta()).just_finished()
     {
-        if let Some(slot) = bar.customer_slots.get_random_empty_slot() {
-            slot.customer = Some(generate_random_customer(&textures));
+        // Nobody comes in if there is no drink to order
+        if let Some(customer) = generate_random_customer(
+            &drinks,
+            &archetypes,
+            &modifiers,
+            &order_config,
+            player_stats.reputation_level,
+            &mut game_rng,
+        ) {
+            let customer = if queue_length == 0 {
+                bar.seat_customer(customer, &mut game_rng)
+                    .err()
+                    .map(|customer| *customer)
+            } else {
+                Some(customer)
+            };
+            if let Some(customer) = customer {
+                let patience = Timer::from_seconds(queue_config.patience, TimerMode::Once);
+                join_queue(&mut commands, customer, queue_length, patience);
+            }
         }
+
         bar.customer_spawn_timer.reset();
-        let rand_next_customer_time =
-            rand::thread_rng().gen_range(customers_stats.customers_spawn_gap.clone());
+        let rand_next_customer_time = game_rng
+            .stream(RngStream::Customers)
+            .gen_range(customers_stats.customers_spawn_gap.clone());
         bar.customer_spawn_timer
-            .set_duration(Duration::from_secs(rand_next_customer_time));
+            .set_duration(Duration::from_secs_f32(
+                rand_next_customer_time as f32 * pacing.spawn_gap_factor(),
+            ));
+    }
+}
+
+fn add_purchased_seats(
+    mut bar_q: Query<&mut Bar>,
+    bar_config: Res<BarConfig>,
+    modifiers: Res<Modifiers>,
+) {
+    for mut bar in bar_q.iter_mut() {
+        bar.add_slots(&bar_config, modifiers.slot_count(bar_config.slot_count));
     }
 }
 
 fn spawn_customers_in_slots(mut commands: Commands, mut bar_q: Query<&mut Bar>) {
     let mut bar = bar_q.single_mut();
 
-    if bar.customer_slots.left.customer.is_some() && !bar.customer_slots.left.spawned {
-        let mut customer = bar.customer_slots.left.customer.take().unwrap();
-        customer.sprite_bundle.transform.translation = SLOT_LEFT_SPAWN_POINT;
-        commands.spawn(customer).insert(CustomerSlotMarker::Left);
-        bar.customer_slots.left.spawned = true;
-    }
-    if bar.customer_slots.middle.customer.is_some() && !bar.customer_slots.middle.spawned {
-        let mut customer = bar.customer_slots.middle.customer.take().unwrap();
-        customer.sprite_bundle.transform.translation = SLOT_MIDDLE_SPAWN_POINT;
-        commands.spawn(customer).insert(CustomerSlotMarker::Middle);
-        bar.customer_slots.middle.spawned = true;
+    for (index, slot) in bar.customer_slots.0.iter_mut().enumerate() {
+        if !slot.spawned {
+            if let Some(mut customer) = slot.customer.take() {
+                customer.sprite_bundle.transform.translation = slot.spawn_point;
+                commands.spawn(customer).insert(CustomerSlotMarker(index));
+                slot.spawned = true;
+            }
+        }
     }
-    if bar.customer_slots.right.customer.is_some() && !bar.customer_slots.right.spawned {
-        let mut customer = bar.customer_slots.right.customer.take().unwrap();
-        customer.sprite_bundle.transform.translation = SLOT_RIGHT_SPAWN_POINT;
-        commands.spawn(customer).insert(CustomerSlotMarker::Right);
-        bar.customer_slots.right.spawned = true;
+}
+
+/// Everything needed to pour a drink from a barrel
+#[derive(SystemParam)]
+struct DrinkSupply<'w, 's> {
+    tray: ResMut<'w, Tray>,
+    tray_config: Res<'w, TrayConfig>,
+    modifiers: Res<'w, Modifiers>,
+    stock: ResMut<'w, Stock>,
+    stock_config: Res<'w, StockConfig>,
+    glasses: ResMut<'w, Glasses>,
+    pour_mode: Res<'w, PourMode>,
+    pour_config: Res<'w, PourConfig>,
+    pouring_glasses_q: Query<'w, 's, (), With<PouringGlass>>,
+}
+
+impl DrinkSupply<'_, '_> {
+    /// Starts pouring `drink` from its barrel at `barrel_position`, or puts it on the tray right away with
+    /// `PourMode::Instant`. Nothing happens while another glass is being poured, with the tray full, without clean
+    /// glasses or with the barrel empty.
+    fn pour(&mut self, commands: &mut Commands, drink: Drink, barrel_position: Vec3) {
+        if !self.pouring_glasses_q.is_empty()
+            || self.tray.len() >= self.modifiers.tray_capacity(self.tray_config.base_capacity)
+            || self.glasses.clean == 0
+            || !self.stock.take(drink, &self.stock_config)
+        {
+            return;
+        }
+        self.glasses.take_clean();
+
+        match *self.pour_mode {
+            PourMode::Pour => start_pour(commands, &self.pour_config, drink, barrel_position),
+            PourMode::Instant => self.tray.push(PouredDrink {
+                drink,
+                quality: PourQuality::Instant,
+            }),
+        }
     }
 }
 
@@ -260,18 +562,24 @@ fn handle_bar_interactible_click(
         (With<MainCameraIngame>, Without<InteractibleAction>),
     >,
     interactibles_q: Query<
-        (Entity, &InteractibleAction),
-        (With<ClickedInteractible>, Without<Customer>),
+        (Entity, &InteractibleAction, &Transform),
+        (
+            With<ClickedInteractible>,
+            Without<Customer>,
+            Without<Sink>,
+            Without<DirtyGlass>,
+        ),
     >,
     mut active_interactibles_q: Query<&mut ActiveInteractibleActions>,
     mut ignored_interactibles_q: Query<&mut IgnoredInteractibleActions>,
-    mut drink_in_hand: ResMut<DrinkInHand>,
+    drinks: Res<Drinks>,
+    mut drink_supply: DrinkSupply,
 ) {
     let mut move_camera_to = move_camera_to_q.single_mut();
     let mut active_interactibles = active_interactibles_q.single_mut();
     let mut ignored_interactibles = ignored_interactibles_q.single_mut();
 
-    for (entity, interactible_action) in interactibles_q.iter() {
+    for (entity, interactible_action, transform) in interactibles_q.iter() {
         commands.entity(entity).remove::<ClickedInteractible>(); // Reset clicked
         match *interactible_action {
             InteractibleAction::Bar => {
@@ -282,9 +590,11 @@ fn handle_bar_interactible_click(
                 // Ignore Customer
                 // ignored_interactibles.0.push(InteractibleAction::Customer);
                 // Stop ignoring Barrels
-                InteractibleAction::get_barrels().iter().for_each(|barrel| {
-                    remove_value_from_vec(*barrel, &mut ignored_interactibles.0)
-                });
+                InteractibleAction::get_barrels(&drinks)
+                    .iter()
+                    .for_each(|barrel| {
+                        remove_value_from_vec(*barrel, &mut ignored_interactibles.0)
+                    });
                 // Activate ExitBar
                 active_interactibles.0.push(InteractibleAction::ExitBar);
             }
@@ -293,7 +603,7 @@ fn handle_bar_interactible_click(
                 // Deactivate ExitBar
                 remove_value_from_vec(InteractibleAction::ExitBar, &mut active_interactibles.0);
                 // Ignore Barrels
-                InteractibleAction::get_barrels()
+                InteractibleAction::get_barrels(&drinks)
                     .iter()
                     .for_each(|barrel| ignored_interactibles.0.push(*barrel));
                 // Stop ignoring Customer
@@ -302,11 +612,14 @@ fn handle_bar_interactible_click(
                 active_interactibles.0.push(InteractibleAction::Bar);
             }
             InteractibleAction::Barrel(drink) => {
-                drink_in_hand.0 = Some(drink);
+                drink_supply.pour(&mut commands, drink, transform.translation);
             }
             InteractibleAction::Customer => {
                 unreachable!("Customers should be ignored in this query")
             }
+            InteractibleAction::Sink | InteractibleAction::DirtyGlass => {
+                unreachable!("Handled by `glasses::handle_glass_click`, ignored in this query")
+            }
             InteractibleAction::_None => {}
         }
     }
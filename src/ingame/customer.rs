@@ -1,22 +1,44 @@
+use bevy::ecs::system::SystemParam;
 use bevy::prelude::*;
-use rand::seq::{IteratorRandom, SliceRandom};
+use rand::seq::SliceRandom;
 
-use crate::{loading::TextureAssets, ScaleByAssetResolution};
+use crate::{
+    archetypes::{Archetype, Archetypes},
+    drinks::Drinks,
+    Difficulty, ScaleByAssetResolution,
+};
 
 use super::{
-    bar::{Bar, CustomerSlotMarker, Drink, BAR_CUSTOMER_HIDDEN_Y, BAR_CUSTOMER_TARGET_Y},
-    ClickedInteractible, CustomersStats, DrinkInHand, IngameState, InteractibleAction,
-    InteractibleBundle, InteractionSpriteColors, OnIngameScreen, PlayerStats,
+    bar::{Bar, CustomerSlotMarker, BAR_CUSTOMER_HIDDEN_Y, BAR_CUSTOMER_TARGET_Y},
+    difficulty::Pacing,
+    game_mode::CurrentRules,
+    glasses::leave_dirty_glasses,
+    modifiers::Modifiers,
+    order::{Order, OrderConfig, WrongDrinkConfig},
+    rng::{GameRng, RngStream},
+    save::{SavedCustomer, SavedCustomerState, SavedQueuedCustomer},
+    tips::{spawn_tip_popup, TipConfig},
+    tray::Tray,
+    workday::DayStats,
+    ClickedInteractible, CustomersStats, IngameState, InteractibleAction, InteractibleBundle,
+    InteractionSpriteColors, OnIngameScreen, PlayerStats,
 };
 
 pub struct CustomerPlugin;
 
 const CUSTOMER_SLIDE_SPEED: f32 = 810.;
 const CUSTOMER_DRINKING_DURATION: f32 = 1.;
+/// Color of the ticket lines already delivered
+const TICKET_DONE_COLOR: Color = Color::rgb(0.5, 0.5, 0.5);
+const WRONG_DRINK_COLOR: Color = Color::rgb(1., 0.2, 0.2);
 
 impl Plugin for CustomerPlugin {
     fn build(&self, app: &mut App) {
         app //
+            .init_resource::<OrderConfig>()
+            .init_resource::<WrongDrinkConfig>()
+            .add_event::<CustomerLost>()
+            .add_event::<CustomerServed>()
             .add_systems(
                 Update,
                 (
@@ -27,6 +49,14 @@ impl Plugin for CustomerPlugin {
     }
 }
 
+/// Sent when a customer leaves without being served, from the bar or from the queue
+#[derive(Event)]
+pub struct CustomerLost;
+
+/// Sent when a customer's whole order is delivered
+#[derive(Event)]
+pub struct CustomerServed;
+
 #[derive(Component)]
 struct OrderPopup(Timer);
 
@@ -38,24 +68,31 @@ struct OrderPopupBundle {
 }
 
 impl OrderPopupBundle {
-    fn new(drink: Drink, translation: Vec3, color: Color, extra_z: f32, duration: f32) -> Self {
+    /// One text line per `(text, color)` of `lines`
+    fn new(lines: Vec<(String, Color)>, translation: Vec3, extra_z: f32, duration: f32) -> Self {
+        let line_count = lines.len();
+        let sections = lines
+            .into_iter()
+            .enumerate()
+            .map(|(index, (text, color))| TextSection {
+                value: if index + 1 < line_count {
+                    text + "\n"
+                } else {
+                    text
+                },
+                style: TextStyle {
+                    font_size: 55.,
+                    color,
+                    ..Default::default()
+                },
+            })
+            .collect();
+
         Self {
             order_popup_marker: OrderPopup(Timer::from_seconds(duration, TimerMode::Once)),
             text_2d_bundle: Text2dBundle {
                 text: Text {
-                    sections: vec![TextSection {
-                        value: match drink {
-                            Drink::Beer => "Beer",
-                            Drink::Wine => "Wine",
-                            Drink::Whiskey => "Whiskey",
-                        }
-                        .to_string(),
-                        style: TextStyle {
-                            font_size: 55.,
-                            color,
-                            ..Default::default()
-                        },
-                    }],
+                    sections,
                     linebreak_behavior: bevy::text::BreakLineOn::NoWrap,
                     alignment: TextAlignment::Center,
                 },
@@ -74,8 +111,22 @@ impl OrderPopupBundle {
 #[derive(Component)]
 pub struct Customer {
     _name: String,
+    archetype: Archetype,
     state: CustomerState,
-    drink: Drink,
+    order: Order,
+}
+
+impl Customer {
+    pub fn to_saved(&self, slot: CustomerSlotMarker, transform: &Transform) -> SavedCustomer {
+        SavedCustomer {
+            slot,
+            name: self._name.clone(),
+            archetype: self.archetype,
+            order: self.order.clone(),
+            state: SavedCustomerState::from(&self.state),
+            translation: transform.translation.to_array(),
+        }
+    }
 }
 
 #[derive(Debug, PartialEq)]
@@ -95,15 +146,22 @@ pub struct CustomerBundle {
 }
 
 impl CustomerBundle {
-    pub fn new(name: &str, drink: Drink, texture: Handle<Image>, transform: Transform) -> Self {
+    pub fn new(
+        name: &str,
+        archetype: Archetype,
+        order: Order,
+        archetypes: &Archetypes,
+        transform: Transform,
+    ) -> Self {
         Self {
             customer: Customer {
                 _name: name.to_string(),
+                archetype,
                 state: CustomerState::Spawning,
-                drink,
+                order,
             },
             sprite_bundle: SpriteBundle {
-                texture,
+                texture: archetypes.get(archetype).texture.clone(),
                 transform,
                 sprite: Sprite {
                     anchor: bevy::sprite::Anchor::BottomCenter, // Ruins interaction_handle
@@ -115,8 +173,76 @@ impl CustomerBundle {
             marker: OnIngameScreen,
         }
     }
+
+    pub fn from_saved(saved: &SavedCustomer, archetypes: &Archetypes) -> Self {
+        let transform = Transform {
+            translation: Vec3::from_array(saved.translation),
+            scale: ScaleByAssetResolution::Res720p.scale(),
+            ..Default::default()
+        };
+        let mut customer_bundle = Self::new(
+            &saved.name,
+            saved.archetype,
+            saved.order.clone(),
+            archetypes,
+            transform,
+        );
+        customer_bundle.customer.state = CustomerState::from(&saved.state);
+        if let CustomerState::Drinking(_) = customer_bundle.customer.state {
+            customer_bundle
+                .interactible_bundle
+                .set_colors(InteractionSpriteColors {
+                    normal: Color::rgb(0.6, 1., 0.6),
+                    highlight: Color::rgb(0.9, 1.3, 0.9),
+                });
+        }
+        customer_bundle
+    }
+
+    pub fn to_saved_queued(&self, place: usize, patience: &Timer) -> SavedQueuedCustomer {
+        SavedQueuedCustomer {
+            place,
+            name: self.customer._name.clone(),
+            archetype: self.customer.archetype,
+            order: self.customer.order.clone(),
+            patience: patience.into(),
+        }
+    }
+
+    pub fn from_saved_queued(saved: &SavedQueuedCustomer, archetypes: &Archetypes) -> Self {
+        let transform = Transform {
+            scale: ScaleByAssetResolution::Res720p.scale(),
+            ..Default::default()
+        };
+        Self::new(
+            &saved.name,
+            saved.archetype,
+            saved.order.clone(),
+            archetypes,
+            transform,
+        )
+    }
 }
 
+/// Rules applied when serving customers
+#[derive(SystemParam)]
+struct ServingRules<'w> {
+    order_config: Res<'w, OrderConfig>,
+    wrong_drink_config: Res<'w, WrongDrinkConfig>,
+    tip_config: Res<'w, TipConfig>,
+    difficulty: Res<'w, Difficulty>,
+    current_rules: CurrentRules<'w>,
+    pacing: Pacing<'w>,
+}
+
+/// Customers leaving the bar, served or not
+#[derive(SystemParam)]
+struct CustomerEvents<'w> {
+    lost: EventWriter<'w, CustomerLost>,
+    served: EventWriter<'w, CustomerServed>,
+}
+
+#[allow(clippy::too_many_arguments)]
 fn customers_system(
     mut commands: Commands,
     time: Res<Time>,
@@ -131,8 +257,15 @@ fn customers_system(
     )>,
     customers_stats: Res<CustomersStats>,
     mut bar_q: Query<&mut Bar>,
-    mut drink_in_hand: ResMut<DrinkInHand>,
+    mut tray: ResMut<Tray>,
     mut player_stats: ResMut<PlayerStats>,
+    mut day_stats: ResMut<DayStats>,
+    modifiers: Res<Modifiers>,
+    drinks: Res<Drinks>,
+    archetypes: Res<Archetypes>,
+    serving_rules: ServingRules,
+    mut customer_events: CustomerEvents,
+    mut game_rng: ResMut<GameRng>,
 ) {
     for (
         entity,
@@ -147,20 +280,34 @@ fn customers_system(
             CustomerState::Spawning => {
                 transform.translation.y += CUSTOMER_SLIDE_SPEED * time.delta_seconds();
                 if transform.translation.y >= BAR_CUSTOMER_TARGET_Y {
+                    // Bigger orders give more time
+                    let extra_drinks = customer.order.drink_count().saturating_sub(1);
                     customer.state = CustomerState::Waiting(Timer::from_seconds(
-                        customers_stats.customers_wait_duration,
+                        modifiers.wait_duration(
+                            customers_stats.customers_wait_duration
+                                * serving_rules.pacing.wait_duration_factor(),
+                        ) * archetypes.get(customer.archetype).patience
+                            * (1.
+                                + serving_rules.order_config.extra_wait_per_drink
+                                    * extra_drinks as f32),
                         TimerMode::Once,
                     ));
                 }
             }
             CustomerState::Waiting(timer) => {
+                // Customers wait forever in the game modes without patience
+                let patience = serving_rules.current_rules.rules().patience;
+
                 // Turn redder the closer the timer is to finishing
                 interaction_sprite_colors.normal =
                     Color::rgb(1., 1. * timer.percent_left(), 1. * timer.percent_left());
                 interaction_sprite_colors.highlight =
                     Color::rgb(1.3, 1.3 * timer.percent_left(), 1.3 * timer.percent_left());
 
-                transform.translation.y -= 10. * time.delta_seconds();
+                // Sink as the timer runs, which never happens without patience
+                if patience {
+                    transform.translation.y -= 10. * time.delta_seconds();
+                }
 
                 // 180. = oscillation frequency // 4. = oscillation range
                 transform.translation.x += (timer.percent().powf(2.) * 180.).cos()
@@ -169,52 +316,130 @@ fn customers_system(
                     * timer.percent().powf(2.)
                     * time.delta_seconds();
 
-                if timer.tick(time.delta()).just_finished() {
+                if patience && timer.tick(time.delta()).just_finished() {
                     // Reset streak on failed drink delivery
                     player_stats.streak = 0;
+                    day_stats.customers_lost += 1;
+                    customer_events.lost.send(CustomerLost);
                     customer.state = CustomerState::Leaving;
+                    // The drinks delivered so far leave their glasses behind
+                    let delivered = customer.order.delivered_count();
+                    if delivered > 0 {
+                        leave_dirty_glasses(
+                            &mut commands,
+                            &mut game_rng,
+                            transform.translation.x,
+                            delivered,
+                        );
+                    }
                 } else if clicked.is_some() {
                     commands.entity(entity).remove::<ClickedInteractible>(); // Reset clicked
+                    let percent_left = if patience {
+                        timer.percent_left() as f64
+                    } else {
+                        serving_rules.tip_config.no_patience_wait_left
+                    };
 
-                    // Show order popup on customer click
-                    spawn_popup(
-                        &mut commands,
-                        &customer,
-                        &transform,
-                        &interaction_sprite_colors,
-                        1.,
-                        1.,
-                    );
+                    // Tick the selected drink of the tray off the order
+                    let drink = tray.take_selected();
+                    let delivered = drink.is_some_and(|poured_drink| {
+                        customer
+                            .order
+                            .deliver(poured_drink.drink, poured_drink.quality)
+                    });
 
-                    if let Some(drink) = drink_in_hand.0.take() {
-                        if drink == customer.drink {
-                            transform.translation.y = BAR_CUSTOMER_TARGET_Y;
-                            interaction_sprite_colors.normal = Color::rgb(0.6, 1., 0.6);
-                            interaction_sprite_colors.highlight = Color::rgb(0.9, 1.3, 0.9);
-                            customer.state = CustomerState::Drinking(Timer::from_seconds(
-                                CUSTOMER_DRINKING_DURATION,
-                                TimerMode::Once,
-                            ));
-
-                            // Show order popup on Successful drink delivery
-                            spawn_popup(
-                                &mut commands,
-                                &customer,
-                                &transform,
-                                &interaction_sprite_colors,
-                                10.,
-                                CUSTOMER_DRINKING_DURATION,
-                            );
-
-                            // Add money, streak and reputation
-                            player_stats.money +=
-                                drink.get_price() * (player_stats.streak as f64 / 2.).max(1.);
-                            player_stats.streak += 1;
-                            if player_stats.streak > player_stats.highest_streak {
-                                player_stats.highest_streak = player_stats.streak;
-                            }
-                            player_stats.reputation_progress += 1;
+                    if drink.is_some() && !delivered {
+                        // Wrong drink, it is wasted
+                        let rules = serving_rules
+                            .wrong_drink_config
+                            .rules(*serving_rules.difficulty);
+                        player_stats.streak =
+                            (player_stats.streak as f32 * rules.streak_kept) as u32;
+                        if let CustomerState::Waiting(timer) = &mut customer.state {
+                            let elapsed = timer.elapsed()
+                                + timer.remaining().mul_f32(rules.wait_cut.clamp(0., 1.));
+                            timer.set_elapsed(elapsed);
                         }
+                        day_stats.wasted_drinks += 1;
+                        leave_dirty_glasses(
+                            &mut commands,
+                            &mut game_rng,
+                            transform.translation.x,
+                            1,
+                        );
+
+                        spawn_wrong_drink_popup(
+                            &mut commands,
+                            &transform,
+                            customer.order.items().count(),
+                        );
+                    }
+
+                    if delivered && customer.order.is_fulfilled() {
+                        transform.translation.y = BAR_CUSTOMER_TARGET_Y;
+                        interaction_sprite_colors.normal = Color::rgb(0.6, 1., 0.6);
+                        interaction_sprite_colors.highlight = Color::rgb(0.9, 1.3, 0.9);
+                        customer.state = CustomerState::Drinking(Timer::from_seconds(
+                            CUSTOMER_DRINKING_DURATION,
+                            TimerMode::Once,
+                        ));
+
+                        // Show order popup on Successful drink delivery
+                        spawn_popup(
+                            &mut commands,
+                            &drinks,
+                            &customer,
+                            &transform,
+                            &interaction_sprite_colors,
+                            10.,
+                            CUSTOMER_DRINKING_DURATION,
+                        );
+
+                        // Add money, streak and reputation for the whole order
+                        let order_price: f64 = customer
+                            .order
+                            .items()
+                            .map(|item| {
+                                modifiers.drink_price(&drinks, item.drink) * item.quantity as f64
+                            })
+                            .sum();
+                        let tip = serving_rules.tip_config.tip(
+                            order_price,
+                            percent_left,
+                            archetypes.get(customer.archetype).tip,
+                            customer.order.tip_factor(),
+                            player_stats.streak,
+                        );
+                        if tip > 0. {
+                            spawn_tip_popup(&mut commands, transform.translation, tip);
+                        }
+                        player_stats.money += order_price + tip;
+                        player_stats.revenue += order_price;
+                        player_stats.tips += tip;
+                        player_stats.streak += 1;
+                        if player_stats.streak > player_stats.highest_streak {
+                            player_stats.highest_streak = player_stats.streak;
+                        }
+                        player_stats.reputation_progress += 1;
+
+                        day_stats.customers_served += 1;
+                        day_stats.money_earned += order_price + tip;
+                        day_stats.tips_earned += tip;
+                        day_stats.highest_streak =
+                            day_stats.highest_streak.max(player_stats.streak);
+                        day_stats.reputation_gained += 1;
+                        customer_events.served.send(CustomerServed);
+                    } else {
+                        // Show order popup on customer click
+                        spawn_popup(
+                            &mut commands,
+                            &drinks,
+                            &customer,
+                            &transform,
+                            &interaction_sprite_colors,
+                            1.,
+                            1.,
+                        );
                     }
                 }
             }
@@ -227,6 +452,12 @@ fn customers_system(
 
                 if timer.tick(time.delta()).just_finished() {
                     customer.state = CustomerState::Leaving;
+                    leave_dirty_glasses(
+                        &mut commands,
+                        &mut game_rng,
+                        transform.translation.x,
+                        customer.order.drink_count(),
+                    );
                 }
             }
             CustomerState::Leaving => {
@@ -243,18 +474,22 @@ fn customers_system(
 // ToDo find a better way that dont spawn multiple popups and or dont collide in z axis
 fn spawn_popup(
     commands: &mut Commands,
+    drinks: &Drinks,
     customer: &Customer,
     transform: &Transform,
     interaction_sprite_colors: &InteractionSpriteColors,
     extra_z: f32,
     duration: f32,
 ) {
-    // Background
+    let ticket = customer.order.ticket(drinks);
+    let fulfilled = customer.order.is_fulfilled();
+
+    // Background, one line high per drink of the ticket
     commands
         .spawn(SpriteBundle {
             sprite: Sprite {
                 color: Color::BLACK,
-                custom_size: Some(Vec2::new(200., 100.)),
+                custom_size: Some(Vec2::new(260., 40. + 60. * ticket.len() as f32)),
                 ..default()
             },
             transform: Transform::from_translation(Vec3::new(
@@ -268,14 +503,35 @@ fn spawn_popup(
         .insert(OrderPopup(Timer::from_seconds(duration, TimerMode::Once)));
     // Popup text
     commands.spawn(OrderPopupBundle::new(
-        customer.drink,
+        ticket
+            .into_iter()
+            .map(|(line, done)| {
+                let color = if done && !fulfilled {
+                    TICKET_DONE_COLOR
+                } else {
+                    interaction_sprite_colors.normal
+                };
+                (line, color)
+            })
+            .collect(),
         transform.translation,
-        interaction_sprite_colors.normal,
         extra_z,
         duration,
     ));
 }
 
+/// Shown above the order ticket, which has `ticket_lines` lines
+fn spawn_wrong_drink_popup(commands: &mut Commands, transform: &Transform, ticket_lines: usize) {
+    let mut popup = OrderPopupBundle::new(
+        vec![("Wrong!".to_string(), WRONG_DRINK_COLOR)],
+        transform.translation,
+        20.,
+        1.,
+    );
+    popup.text_2d_bundle.transform.translation.y += 60. + 30. * ticket_lines as f32;
+    commands.spawn(popup);
+}
+
 fn handle_order_popup(
     mut commands: Commands,
     time: Res<Time>,
@@ -290,39 +546,39 @@ fn handle_order_popup(
     }
 }
 
-pub fn generate_random_customer(textures: &Res<TextureAssets>) -> CustomerBundle {
-    let mut rng = rand::thread_rng();
-    let name = CUSTOMER_NAMES.choose(&mut rng).unwrap_or(&"John");
-    let drink = Drink::iterator().choose(&mut rng).unwrap().0;
-    let texture = CustomerAssets::iterator()
-        .choose(&mut rng)
-        .unwrap()
-        .get_texture(textures);
+/// Customer with a random order of the unlocked drinks. None if the catalogue has no drinks to order.
+pub fn generate_random_customer(
+    drinks: &Drinks,
+    archetypes: &Archetypes,
+    modifiers: &Modifiers,
+    order_config: &OrderConfig,
+    reputation_level: u32,
+    game_rng: &mut GameRng,
+) -> Option<CustomerBundle> {
+    let rng = game_rng.stream(RngStream::Customers);
+    let name = CUSTOMER_NAMES.choose(rng).unwrap_or(&"John");
+    let archetype = archetypes.choose(rng);
+    let unlocked_drinks = modifiers
+        .unlocked_drinks(drinks, reputation_level)
+        .collect::<Vec<_>>();
+    let order = Order::random(
+        order_config,
+        archetypes.get(archetype),
+        &unlocked_drinks,
+        reputation_level,
+        rng,
+    )?;
     let transform = Transform {
         translation: Vec3::new(0.0, 0.0, 0.0),
         scale: ScaleByAssetResolution::Res720p.scale(),
         ..Default::default()
     };
 
-    CustomerBundle::new(name, drink, texture, transform)
+    Some(CustomerBundle::new(
+        name, archetype, order, archetypes, transform,
+    ))
 }
 
 pub const CUSTOMER_NAMES: [&str; 10] = [
     "John", "Jane", "Jack", "Jill", "James", "Jenny", "Jasper", "Jade", "Jared", "Jasmine",
 ];
-
-enum CustomerAssets {
-    Customer1,
-}
-
-impl CustomerAssets {
-    pub fn get_texture(&self, textures: &Res<TextureAssets>) -> Handle<Image> {
-        match self {
-            CustomerAssets::Customer1 => textures.customer1.clone(),
-        }
-    }
-
-    fn iterator() -> impl Iterator<Item = Self> {
-        vec![CustomerAssets::Customer1].into_iter()
-    }
-}
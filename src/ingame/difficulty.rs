@@ -0,0 +1,195 @@
+//! Difficulty presets and adaptive difficulty.
+//!
+//! The `Difficulty` chosen in the settings picks a [`DifficultyPreset`] that scales how long customers wait and how
+//! often they come in, on top of `CustomersStats`. With `AdaptiveDifficulty::On` the [`AdaptivePacing`] also
+//! watches the success rate of the last customers (served or lost) and makes the customers more relaxed or more
+//! demanding to keep it in the target range of the [`AdaptiveConfig`]. [`Pacing`] combines both for the systems
+//! that use `CustomersStats`.
+
+use std::collections::VecDeque;
+
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{AdaptiveDifficulty, Difficulty};
+
+use super::{
+    customer::{CustomerLost, CustomerServed},
+    IngameState,
+};
+
+pub struct DifficultyPlugin;
+
+impl Plugin for DifficultyPlugin {
+    fn build(&self, app: &mut App) {
+        app //
+            .init_resource::<DifficultyConfig>()
+            .init_resource::<AdaptivePacing>()
+            .add_systems(Update, adapt_pacing.run_if(in_state(IngameState::Running)));
+    }
+}
+
+pub struct DifficultyPreset {
+    /// Multiplies `CustomersStats.customers_wait_duration`
+    pub wait_duration_factor: f32,
+    /// Multiplies the gaps picked from `CustomersStats.customers_spawn_gap`
+    pub spawn_gap_factor: f32,
+}
+
+/// How the adaptive difficulty reacts to the success rate.
+pub struct AdaptiveConfig {
+    /// Number of recent customers the success rate is computed from
+    pub window: usize,
+    /// Below this success rate the customers get more relaxed
+    pub min_success_rate: f32,
+    /// Above this success rate the customers get more demanding
+    pub max_success_rate: f32,
+    /// Change of [`AdaptivePacing::factor`] for every customer while the success rate is out of range
+    pub step: f32,
+    pub min_factor: f32,
+    pub max_factor: f32,
+}
+
+/// [`DifficultyPreset`] for each difficulty and the adaptive difficulty settings.
+#[derive(Resource)]
+pub struct DifficultyConfig {
+    pub easy: DifficultyPreset,
+    pub normal: DifficultyPreset,
+    pub hard: DifficultyPreset,
+    pub adaptive: AdaptiveConfig,
+}
+
+impl Default for DifficultyConfig {
+    fn default() -> Self {
+        Self {
+            easy: DifficultyPreset {
+                wait_duration_factor: 1.4,
+                spawn_gap_factor: 1.3,
+            },
+            normal: DifficultyPreset {
+                wait_duration_factor: 1.,
+                spawn_gap_factor: 1.,
+            },
+            hard: DifficultyPreset {
+                wait_duration_factor: 0.75,
+                spawn_gap_factor: 0.75,
+            },
+            adaptive: AdaptiveConfig {
+                window: 10,
+                min_success_rate: 0.6,
+                max_success_rate: 0.9,
+                step: 0.05,
+                min_factor: 0.6,
+                max_factor: 1.5,
+            },
+        }
+    }
+}
+
+impl DifficultyConfig {
+    pub fn preset(&self, difficulty: Difficulty) -> &DifficultyPreset {
+        match difficulty {
+            Difficulty::Easy => &self.easy,
+            Difficulty::Normal => &self.normal,
+            Difficulty::Hard => &self.hard,
+        }
+    }
+}
+
+/// State of the adaptive difficulty. Part of the save game.
+#[derive(Resource, Clone, Serialize, Deserialize)]
+pub struct AdaptivePacing {
+    /// Outcomes of the last customers, true if they were served
+    recent: VecDeque<bool>,
+    /// Multiplies the wait duration and the spawn gaps, above 1 is more relaxed
+    factor: f32,
+}
+
+impl Default for AdaptivePacing {
+    fn default() -> Self {
+        Self {
+            recent: VecDeque::new(),
+            factor: 1.,
+        }
+    }
+}
+
+impl AdaptivePacing {
+    fn record(&mut self, served: bool, adaptive_config: &AdaptiveConfig) {
+        self.recent.push_back(served);
+        while self.recent.len() > adaptive_config.window {
+            self.recent.pop_front();
+        }
+        // Wait for a few customers before judging
+        if self.recent.len() < adaptive_config.window / 2 {
+            return;
+        }
+
+        let success_rate =
+            self.recent.iter().filter(|served| **served).count() as f32 / self.recent.len() as f32;
+        if success_rate < adaptive_config.min_success_rate {
+            self.factor += adaptive_config.step;
+        } else if success_rate > adaptive_config.max_success_rate {
+            self.factor -= adaptive_config.step;
+        }
+        self.factor = self
+            .factor
+            .clamp(adaptive_config.min_factor, adaptive_config.max_factor);
+    }
+}
+
+/// Difficulty preset and adaptive difficulty, applied on top of `CustomersStats`
+#[derive(SystemParam)]
+pub struct Pacing<'w> {
+    difficulty: Res<'w, Difficulty>,
+    adaptive_difficulty: Res<'w, AdaptiveDifficulty>,
+    difficulty_config: Res<'w, DifficultyConfig>,
+    adaptive_pacing: Res<'w, AdaptivePacing>,
+}
+
+impl Pacing<'_> {
+    /// Multiplies `CustomersStats.customers_wait_duration`
+    pub fn wait_duration_factor(&self) -> f32 {
+        self.difficulty_config
+            .preset(*self.difficulty)
+            .wait_duration_factor
+            * self.adaptive_factor()
+    }
+
+    /// Multiplies the gaps picked from `CustomersStats.customers_spawn_gap`
+    pub fn spawn_gap_factor(&self) -> f32 {
+        self.difficulty_config
+            .preset(*self.difficulty)
+            .spawn_gap_factor
+            * self.adaptive_factor()
+    }
+
+    fn adaptive_factor(&self) -> f32 {
+        match *self.adaptive_difficulty {
+            AdaptiveDifficulty::On => self.adaptive_pacing.factor,
+            AdaptiveDifficulty::Off => 1.,
+        }
+    }
+}
+
+fn adapt_pacing(
+    mut customer_served_events: EventReader<CustomerServed>,
+    mut customer_lost_events: EventReader<CustomerLost>,
+    adaptive_difficulty: Res<AdaptiveDifficulty>,
+    difficulty_config: Res<DifficultyConfig>,
+    mut adaptive_pacing: ResMut<AdaptivePacing>,
+) {
+    let served = customer_served_events.read().count();
+    let lost = customer_lost_events.read().count();
+    if *adaptive_difficulty == AdaptiveDifficulty::Off || served + lost == 0 {
+        return;
+    }
+
+    for _ in 0..served {
+        adaptive_pacing.record(true, &difficulty_config.adaptive);
+    }
+    for _ in 0..lost {
+        adaptive_pacing.record(false, &difficulty_config.adaptive);
+    }
+}
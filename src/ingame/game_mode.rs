@@ -0,0 +1,149 @@
+//! Rules of the game modes.
+//!
+//! The `GameMode` chosen in the menu gives the [`GameModeRules`] of the session (see [`GameModeConfig`]): how patient
+//! and frequent the customers are at the start, whether they run out of patience, whether the days go by, the lives
+//! and what ends the session. Endless never ends. Career is lost when running out of lives and won at the end of
+//! its last day. Time Attack is about earning as much money as possible before the time limit. Zen has no timers.
+
+use std::ops::Range;
+
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+
+use crate::GameMode;
+
+use super::{game_over::GameOverReason, CustomersStats, IngameState, PlayerStats};
+
+pub struct GameModePlugin;
+
+impl Plugin for GameModePlugin {
+    fn build(&self, app: &mut App) {
+        app //
+            .init_resource::<GameModeConfig>()
+            .add_systems(
+                Update,
+                tick_play_time.run_if(in_state(IngameState::Running)),
+            );
+    }
+}
+
+pub struct GameModeRules {
+    /// `CustomersStats.customers_wait_duration` at the start of the session
+    pub customers_wait_duration: f32,
+    /// `CustomersStats.customers_spawn_gap` at the start of the session
+    pub customers_spawn_gap: Range<u64>,
+    /// Customers leave when they run out of patience, at the bar or in the queue
+    pub patience: bool,
+    /// The tavern closes at the end of every day
+    pub day_cycle: bool,
+    /// Lives at the start of the session. Without them lost customers only cost streak and reputation
+    pub lives: Option<u32>,
+    /// Seconds played before the session is over
+    pub time_limit: Option<f32>,
+    /// The session is won at the end of this day
+    pub days_to_win: Option<u32>,
+}
+
+impl GameModeRules {
+    pub fn customers_stats(&self) -> CustomersStats {
+        CustomersStats {
+            customers_wait_duration: self.customers_wait_duration,
+            customers_spawn_gap: self.customers_spawn_gap.clone(),
+        }
+    }
+}
+
+/// [`GameModeRules`] for each game mode.
+#[derive(Resource)]
+pub struct GameModeConfig {
+    pub endless: GameModeRules,
+    pub career: GameModeRules,
+    pub time_attack: GameModeRules,
+    pub zen: GameModeRules,
+}
+
+impl Default for GameModeConfig {
+    fn default() -> Self {
+        Self {
+            endless: GameModeRules {
+                customers_wait_duration: 3.,
+                customers_spawn_gap: 0..3,
+                patience: true,
+                day_cycle: true,
+                lives: None,
+                time_limit: None,
+                days_to_win: None,
+            },
+            career: GameModeRules {
+                customers_wait_duration: 3.,
+                customers_spawn_gap: 0..3,
+                patience: true,
+                day_cycle: true,
+                lives: Some(3),
+                time_limit: None,
+                days_to_win: Some(7),
+            },
+            time_attack: GameModeRules {
+                customers_wait_duration: 2.5,
+                customers_spawn_gap: 0..2,
+                patience: true,
+                day_cycle: false,
+                lives: None,
+                time_limit: Some(180.),
+                days_to_win: None,
+            },
+            zen: GameModeRules {
+                customers_wait_duration: 3.,
+                customers_spawn_gap: 1..4,
+                patience: false,
+                day_cycle: false,
+                lives: None,
+                time_limit: None,
+                days_to_win: None,
+            },
+        }
+    }
+}
+
+impl GameModeConfig {
+    pub fn rules(&self, game_mode: GameMode) -> &GameModeRules {
+        match game_mode {
+            GameMode::Endless => &self.endless,
+            GameMode::Career => &self.career,
+            GameMode::TimeAttack => &self.time_attack,
+            GameMode::Zen => &self.zen,
+        }
+    }
+}
+
+/// Rules of the game mode being played
+#[derive(SystemParam)]
+pub struct CurrentRules<'w> {
+    game_mode: Res<'w, GameMode>,
+    game_mode_config: Res<'w, GameModeConfig>,
+}
+
+impl CurrentRules<'_> {
+    pub fn rules(&self) -> &GameModeRules {
+        self.game_mode_config.rules(*self.game_mode)
+    }
+}
+
+fn tick_play_time(
+    time: Res<Time>,
+    current_rules: CurrentRules,
+    mut player_stats: ResMut<PlayerStats>,
+    mut game_over_reason: ResMut<GameOverReason>,
+    mut ingame_state: ResMut<NextState<IngameState>>,
+) {
+    player_stats.play_time += time.delta_seconds();
+
+    if current_rules
+        .rules()
+        .time_limit
+        .is_some_and(|time_limit| player_stats.play_time >= time_limit)
+    {
+        *game_over_reason = GameOverReason::TimeUp;
+        ingame_state.set(IngameState::GameOver);
+    }
+}
@@ -0,0 +1,320 @@
+//! Lives and game over.
+//!
+//! In the game modes with lives (see `GameModeRules::lives`) every customer that leaves without being served, from
+//! the bar or from the queue, costs one of the [`Lives`]. Once none are left the game moves to
+//! `IngameState::GameOver`, a screen with the final stats from where the player retries with a new game of the same
+//! mode or goes back to the main menu. The other game modes end there too, for the [`GameOverReason`] of the mode.
+//! A finished game is not saved.
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    despawn_screen,
+    menu::{menu_button, ButtonColors},
+    GameState, TEXT_COLOR,
+};
+
+use super::{
+    customer::CustomerLost,
+    game_mode::CurrentRules,
+    rng::GameRng,
+    save::{start_session, SessionStart},
+    workday::{DayStats, Workday},
+    IngameState, MainCameraIngame, OnIngameScreen, PlayerStats, CAMERA_RESOLUTION,
+};
+
+pub struct GameOverPlugin;
+
+impl Plugin for GameOverPlugin {
+    fn build(&self, app: &mut App) {
+        app //
+            .init_resource::<Lives>()
+            .init_resource::<GameOverReason>()
+            .add_systems(
+                OnEnter(GameState::Playing),
+                setup_lives_text.after(start_session),
+            )
+            .add_systems(
+                Update,
+                (
+                    lose_lives.run_if(in_state(IngameState::Running)),
+                    update_lives_text.run_if(resource_changed::<Lives>()),
+                ),
+            )
+            // IngameState::GameOver
+            .add_systems(OnEnter(IngameState::GameOver), setup_game_over)
+            .add_systems(
+                Update,
+                handle_game_over_button.run_if(in_state(IngameState::GameOver)),
+            )
+            .add_systems(
+                OnExit(IngameState::GameOver),
+                despawn_screen::<OnGameOverScreen>,
+            )
+            .add_systems(OnEnter(GameState::Restarting), restart_session);
+    }
+}
+
+// Constants
+const NO_LIVES_LEFT_COLOR: Color = Color::rgb(0.8, 0.1, 0.1);
+
+/// Lives left in the game modes with lives. Part of the save game.
+#[derive(Resource, Default, Clone, Serialize, Deserialize)]
+pub struct Lives(pub u32);
+
+/// Why the session is over. Set before moving to `IngameState::GameOver`
+#[derive(Resource, Default, Clone, Copy, PartialEq, Eq)]
+pub enum GameOverReason {
+    #[default]
+    NoLivesLeft,
+    /// The time limit of the game mode is over
+    TimeUp,
+    /// The last day of the career is over
+    CareerComplete,
+}
+
+impl GameOverReason {
+    fn title(&self) -> &'static str {
+        match self {
+            GameOverReason::NoLivesLeft => "Game Over",
+            GameOverReason::TimeUp => "Time's Up",
+            GameOverReason::CareerComplete => "Career Complete",
+        }
+    }
+}
+
+#[derive(Component)]
+struct LivesText;
+
+#[derive(Component)]
+struct OnGameOverScreen;
+
+#[derive(Component)]
+enum GameOverButtonAction {
+    Retry,
+    MainMenu,
+}
+
+fn setup_lives_text(mut commands: Commands, current_rules: CurrentRules) {
+    if current_rules.rules().lives.is_none() {
+        return;
+    }
+
+    commands
+        .spawn(
+            TextBundle::from_section(
+                "",
+                TextStyle {
+                    font_size: 50.,
+                    color: Color::BLACK,
+                    ..Default::default()
+                },
+            )
+            .with_text_alignment(TextAlignment::Center)
+            .with_background_color(Color::rgba(1., 1., 1., 0.1))
+            .with_style(Style {
+                position_type: PositionType::Absolute,
+                left: Val::Px(0.),
+                top: Val::Px(80.),
+                padding: UiRect::all(Val::Px(10.)),
+                ..Default::default()
+            }),
+        )
+        .insert(LivesText)
+        .insert(OnIngameScreen);
+}
+
+fn update_lives_text(lives: Res<Lives>, mut lives_text_q: Query<&mut Text, With<LivesText>>) {
+    // There is no text in the game modes without lives
+    let Ok(mut lives_text) = lives_text_q.get_single_mut() else {
+        return;
+    };
+    lives_text.sections[0].value = format!("Lives: {}", lives.0);
+    lives_text.sections[0].style.color = if lives.0 <= 1 {
+        NO_LIVES_LEFT_COLOR
+    } else {
+        Color::BLACK
+    };
+}
+
+fn lose_lives(
+    mut customer_lost_events: EventReader<CustomerLost>,
+    current_rules: CurrentRules,
+    mut lives: ResMut<Lives>,
+    mut game_over_reason: ResMut<GameOverReason>,
+    mut ingame_state: ResMut<NextState<IngameState>>,
+) {
+    let lost = customer_lost_events.read().count() as u32;
+    if lost == 0 || current_rules.rules().lives.is_none() {
+        return;
+    }
+
+    lives.0 = lives.0.saturating_sub(lost);
+    if lives.0 == 0 {
+        *game_over_reason = GameOverReason::NoLivesLeft;
+        ingame_state.set(IngameState::GameOver);
+    }
+}
+
+fn setup_game_over(
+    mut commands: Commands,
+    game_over_reason: Res<GameOverReason>,
+    workday: Res<Workday>,
+    player_stats: Res<PlayerStats>,
+    day_stats: Res<DayStats>,
+    game_rng: Res<GameRng>,
+    camera_q: Query<&Transform, With<MainCameraIngame>>,
+) {
+    // Background
+    let camera_transform = camera_q.single();
+    commands
+        .spawn(SpriteBundle {
+            transform: Transform {
+                translation: camera_transform.translation.xy().extend(111.),
+                scale: CAMERA_RESOLUTION.extend(0.),
+                ..Default::default()
+            },
+            sprite: Sprite {
+                color: Color::rgba(0.1, 0., 0., 0.95),
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+        .insert(OnGameOverScreen);
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(25.0),
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::FlexEnd,
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            OnGameOverScreen,
+        ))
+        .with_children(|child_builder| {
+            child_builder.spawn(TextBundle::from_section(
+                game_over_reason.title(),
+                TextStyle {
+                    font_size: 120.,
+                    color: TEXT_COLOR,
+                    ..Default::default()
+                },
+            ));
+        });
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(65.0),
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    align_self: AlignSelf::End,
+                    margin: UiRect::bottom(Val::Vh(10.)),
+                    padding: UiRect::vertical(Val::Vh(10.)),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            OnGameOverScreen,
+        ))
+        .with_children(|child_builder| {
+            let text_style = TextStyle {
+                font_size: 50.0,
+                color: TEXT_COLOR,
+                ..Default::default()
+            };
+
+            for line in [
+                format!("Day {}", workday.day),
+                format!("Money: {:.2}", player_stats.money),
+                format!(
+                    "Drinks: {:.2} | Tips: {:.2}",
+                    player_stats.revenue, player_stats.tips
+                ),
+                format!("Highest streak: {}", player_stats.highest_streak),
+                format!("Reputation: {}", player_stats.reputation_level),
+                format!("Customers served today: {}", day_stats.customers_served),
+                format!("Seed: {}", game_rng.seed()),
+            ] {
+                child_builder.spawn(
+                    TextBundle::from_section(line, text_style.clone()).with_style(Style {
+                        margin: UiRect::bottom(Val::Px(15.)),
+                        ..Default::default()
+                    }),
+                );
+            }
+
+            let button_style = Style {
+                width: Val::Px(300.0),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                margin: UiRect::top(Val::Px(25.)),
+                padding: UiRect::axes(Val::Px(15.), Val::Px(10.)),
+                ..Default::default()
+            };
+
+            menu_button(
+                child_builder,
+                "Retry",
+                GameOverButtonAction::Retry,
+                &Style {
+                    margin: UiRect::top(Val::Px(50.)),
+                    ..button_style.clone()
+                },
+                &ButtonColors {
+                    hovered: Color::rgb(0.3, 0.4, 0.4),
+                    ..Default::default()
+                },
+                &text_style,
+            );
+
+            menu_button(
+                child_builder,
+                "Main Menu",
+                GameOverButtonAction::MainMenu,
+                &button_style,
+                &ButtonColors::default(),
+                &text_style,
+            );
+        });
+}
+
+fn handle_game_over_button(
+    interaction_query: Query<
+        (&Interaction, &GameOverButtonAction),
+        (Changed<Interaction>, With<Button>),
+    >,
+    mut session_start: ResMut<SessionStart>,
+    mut game_state: ResMut<NextState<GameState>>,
+    mut ingame_state: ResMut<NextState<IngameState>>,
+) {
+    for (interaction, game_over_button_action) in &interaction_query {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        // Not through `IngameState::ToMenu`, which would save the lost game again
+        ingame_state.set(IngameState::Diabled);
+        match game_over_button_action {
+            GameOverButtonAction::Retry => {
+                *session_start = SessionStart::NewGame;
+                game_state.set(GameState::Restarting);
+            }
+            GameOverButtonAction::MainMenu => game_state.set(GameState::Menu),
+        }
+    }
+}
+
+/// Leaves `GameState::Restarting` right away, starting the new session
+fn restart_session(mut game_state: ResMut<NextState<GameState>>) {
+    game_state.set(GameState::Playing);
+}
@@ -0,0 +1,236 @@
+//! Glasses and the dish-washing station.
+//!
+//! Every pour uses one of the clean [`Glasses`]. Customers leave their dirty glasses at their seat when they finish
+//! drinking (and the wrong drinks they were handed). Clicking a dirty glass picks it up, and clicking the sink washes
+//! every dirty glass picked up, making them clean again. Dumped drinks go straight back to the dirty glasses carried.
+//! More clean glasses are bought in packs of [`GLASS_PACK_SIZE`] from the shop.
+
+use bevy::prelude::*;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::GameState;
+
+use super::{
+    rng::{GameRng, RngStream},
+    save::start_session,
+    ClickedInteractible, IngameState, InteractibleAction, InteractibleBundle,
+    InteractionSpriteColors, OnIngameScreen,
+};
+
+pub struct GlassesPlugin;
+
+impl Plugin for GlassesPlugin {
+    fn build(&self, app: &mut App) {
+        app //
+            .init_resource::<Glasses>()
+            .add_systems(
+                OnEnter(GameState::Playing),
+                setup_glasses.after(start_session),
+            )
+            .add_systems(
+                Update,
+                (
+                    handle_glass_click.run_if(in_state(IngameState::Running)),
+                    update_glasses_text.run_if(resource_changed::<Glasses>()),
+                ),
+            );
+    }
+}
+
+// Constants
+const STARTING_CLEAN_GLASSES: u32 = 8;
+/// Clean glasses sold together in the shop
+pub const GLASS_PACK_SIZE: u32 = 4;
+pub const GLASS_PACK_COST: f64 = 10.;
+const SINK_POSITION: Vec3 = Vec3::new(-550., -720., 12.);
+const SINK_SIZE: Vec2 = Vec2::new(260., 150.);
+/// Height of the dirty glasses left on the bar counter
+const DIRTY_GLASS_Y: f32 = -600.;
+const DIRTY_GLASS_Z: f32 = 16.;
+const DIRTY_GLASS_SIZE: Vec2 = Vec2::new(40., 60.);
+/// Glasses left at the same seat are spread up to this far from the customer
+const DIRTY_GLASS_SPREAD: f32 = 60.;
+const NO_CLEAN_GLASSES_COLOR: Color = Color::rgb(0.8, 0.1, 0.1);
+
+/// Glass inventory. Part of the save game.
+#[derive(Resource, Clone, Serialize, Deserialize)]
+pub struct Glasses {
+    pub clean: u32,
+    /// Dirty glasses picked up, waiting to be washed
+    pub dirty_carried: u32,
+}
+
+impl Default for Glasses {
+    fn default() -> Self {
+        Self {
+            clean: STARTING_CLEAN_GLASSES,
+            dirty_carried: 0,
+        }
+    }
+}
+
+impl Glasses {
+    /// Takes a clean glass to pour a drink. Returns false if there are none left.
+    pub fn take_clean(&mut self) -> bool {
+        if self.clean == 0 {
+            return false;
+        }
+        self.clean -= 1;
+        true
+    }
+
+    fn wash(&mut self) {
+        self.clean += self.dirty_carried;
+        self.dirty_carried = 0;
+    }
+}
+
+/// Dirty glasses left on the bar counter
+#[derive(Component)]
+pub struct DirtyGlass(pub u32);
+
+/// The sink washing the dirty glasses carried
+#[derive(Component)]
+pub struct Sink;
+
+#[derive(Component)]
+struct GlassesText;
+
+/// Leaves `count` dirty glasses on the bar counter, in front of the customer at `customer_x`
+pub fn leave_dirty_glasses(
+    commands: &mut Commands,
+    game_rng: &mut GameRng,
+    customer_x: f32,
+    count: u32,
+) {
+    let x = customer_x
+        + game_rng
+            .stream(RngStream::Glasses)
+            .gen_range(-DIRTY_GLASS_SPREAD..=DIRTY_GLASS_SPREAD);
+    spawn_dirty_glasses(commands, Vec3::new(x, DIRTY_GLASS_Y, DIRTY_GLASS_Z), count);
+}
+
+pub fn spawn_dirty_glasses(commands: &mut Commands, translation: Vec3, count: u32) {
+    let mut interactible_bundle = InteractibleBundle::new(InteractibleAction::DirtyGlass);
+    interactible_bundle.set_colors(InteractionSpriteColors {
+        normal: Color::rgb(0.55, 0.5, 0.4),
+        highlight: Color::rgb(0.75, 0.7, 0.6),
+    });
+    commands
+        .spawn(SpriteBundle {
+            // The default image is a single pixel, scaled to the glass size to get the right interactible size
+            transform: Transform {
+                translation,
+                scale: DIRTY_GLASS_SIZE.extend(1.),
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+        .insert(DirtyGlass(count))
+        .insert(interactible_bundle)
+        .insert(OnIngameScreen);
+}
+
+fn setup_glasses(mut commands: Commands) {
+    // Sink
+    let mut interactible_bundle = InteractibleBundle::new(InteractibleAction::Sink);
+    interactible_bundle.set_colors(InteractionSpriteColors {
+        normal: Color::rgb(0.45, 0.55, 0.6),
+        highlight: Color::rgb(0.6, 0.72, 0.8),
+    });
+    commands
+        .spawn(SpriteBundle {
+            transform: Transform {
+                translation: SINK_POSITION,
+                scale: SINK_SIZE.extend(1.),
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+        .insert(interactible_bundle)
+        .insert(Sink)
+        .insert(OnIngameScreen);
+    commands
+        .spawn(Text2dBundle {
+            text: Text::from_section(
+                "Sink",
+                TextStyle {
+                    font_size: 55.,
+                    color: Color::BLACK,
+                    ..Default::default()
+                },
+            ),
+            transform: Transform::from_translation(SINK_POSITION + Vec3::Z),
+            ..Default::default()
+        })
+        .insert(OnIngameScreen);
+
+    // GlassesText
+    commands
+        .spawn(
+            TextBundle::from_section(
+                "",
+                TextStyle {
+                    font_size: 50.,
+                    color: Color::BLACK,
+                    ..Default::default()
+                },
+            )
+            .with_text_alignment(TextAlignment::Center)
+            .with_background_color(Color::rgba(1., 1., 1., 0.1))
+            .with_style(Style {
+                position_type: PositionType::Absolute,
+                right: Val::Px(0.),
+                // Above the reputation bar
+                bottom: Val::Px(110.),
+                padding: UiRect::all(Val::Px(10.)),
+                ..Default::default()
+            }),
+        )
+        .insert(GlassesText)
+        .insert(OnIngameScreen);
+}
+
+fn handle_glass_click(
+    mut commands: Commands,
+    interactibles_q: Query<
+        (Entity, &InteractibleAction, Option<&DirtyGlass>),
+        With<ClickedInteractible>,
+    >,
+    mut glasses: ResMut<Glasses>,
+) {
+    for (entity, interactible_action, dirty_glass) in interactibles_q.iter() {
+        match interactible_action {
+            InteractibleAction::DirtyGlass => {
+                if let Some(dirty_glass) = dirty_glass {
+                    glasses.dirty_carried += dirty_glass.0;
+                }
+                commands.entity(entity).despawn_recursive();
+            }
+            InteractibleAction::Sink => {
+                commands.entity(entity).remove::<ClickedInteractible>(); // Reset clicked
+                glasses.wash();
+            }
+            _ => {}
+        }
+    }
+}
+
+fn update_glasses_text(
+    glasses: Res<Glasses>,
+    mut glasses_text_q: Query<&mut Text, With<GlassesText>>,
+) {
+    let Ok(mut glasses_text) = glasses_text_q.get_single_mut() else {
+        return;
+    };
+    glasses_text.sections[0].value = format!(
+        "Clean glasses: {}\nDirty carried: {}",
+        glasses.clean, glasses.dirty_carried
+    );
+    glasses_text.sections[0].style.color = if glasses.clean == 0 {
+        NO_CLEAN_GLASSES_COLOR
+    } else {
+        Color::BLACK
+    };
+}
@@ -5,25 +5,71 @@
 //!
 //! The module also defines various components, resources, and systems used in the in-game functionality.
 //! These include components like `OnIngameScreen`, `ClickedInteractible`, `InteractibleBundle`, etc.
-//! Resources like `DrinkInHand`, `PlayerStats`, `CustomersStats`, etc. are used to store game-related data.
+//! Resources like `Tray`, `PlayerStats`, `CustomersStats`, etc. are used to store game-related data.
 //! Systems like `setup_camera`, `setup_ingame`, `handle_esc`, `interactibles_system`, etc. handle different aspects of the in-game functionality.
 //!
 //! The in-game functionality allows players to interact with various objects in the tavern, serve customers, manage resources, and progress in the game.
 
 mod bar;
 mod customer;
+mod difficulty;
+mod game_mode;
+mod game_over;
+mod glasses;
+mod modifiers;
+mod order;
 mod pause_menu;
+mod pour;
+mod queue;
+mod reputation;
+mod rng;
+mod save;
+mod shop;
+mod stock;
+mod tips;
+mod tray;
+mod workday;
+use crate::actions::{Actions, GameAction};
 use crate::loading::TextureAssets;
+use crate::menu::controls::{
+    apply_key_capture, handle_binding_buttons, stop_key_capture, update_binding_texts,
+    OnControlsScreen,
+};
 use crate::menu::settings::{setting_button_handle, settings_button_colors, OnSettingsMenuScreen};
-use crate::{despawn_screen, GameState, ScaleByAssetResolution, ScreenMode, CAMERA_RESOLUTION};
-use bevy::input::mouse::MouseWheel;
+use crate::{
+    despawn_screen, AdaptiveDifficulty, Difficulty, GameState, PourMode, ScaleByAssetResolution,
+    ScreenMode, CAMERA_RESOLUTION,
+};
 use bevy::prelude::*;
 use bevy::sprite::collide_aabb::collide;
-use bevy::window::PrimaryWindow;
+use serde::{Deserialize, Serialize};
 
-use self::bar::{BarPlugin, Drink};
+use crate::drinks::{Drink, Drinks};
+
+use self::bar::BarPlugin;
 use self::customer::CustomerPlugin;
-use self::pause_menu::{handle_button, settings_pause_setup, setup_pause_menu, OnPauseMenu};
+use self::difficulty::DifficultyPlugin;
+use self::game_mode::GameModePlugin;
+use self::game_over::GameOverPlugin;
+use self::glasses::GlassesPlugin;
+use self::modifiers::ModifiersPlugin;
+use self::pause_menu::{
+    controls_pause_setup, handle_button, settings_pause_setup, setup_pause_button,
+    setup_pause_menu, OnPauseMenu,
+};
+use self::pour::PourPlugin;
+use self::queue::QueuePlugin;
+use self::reputation::ReputationPlugin;
+use self::rng::GameRng;
+use self::save::SavePlugin;
+use self::shop::{ShopOrigin, ShopPlugin};
+use self::stock::StockPlugin;
+use self::tips::TipsPlugin;
+use self::tray::TrayPlugin;
+use self::workday::WorkdayPlugin;
+
+pub use self::bar::BarConfig;
+pub use self::save::{SaveGame, SessionStart};
 
 pub struct IngamePlugin;
 
@@ -32,15 +78,16 @@ const CAMERA_MOVEMENT_SPEED: f32 = 900.;
 #[derive(Component)]
 pub struct OnIngameScreen;
 
-#[derive(Component)]
-struct OnPauseUI;
-
 #[derive(Clone, Copy, Default, Eq, PartialEq, Debug, Hash, States)]
 pub enum IngameState {
     Running,
     Paused,
     Settings,
+    Controls,
     ToMenu,
+    DaySummary,
+    Shop,
+    GameOver,
     #[default]
     Diabled,
 }
@@ -63,6 +110,10 @@ impl InteractibleBundle {
             },
         }
     }
+
+    pub fn set_colors(&mut self, interaction_sprite_colors: InteractionSpriteColors) {
+        self.interaction_sprite_colors = interaction_sprite_colors;
+    }
 }
 
 #[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -71,12 +122,15 @@ pub enum InteractibleAction {
     ExitBar,
     Barrel(Drink),
     Customer,
+    Sink,
+    DirtyGlass,
     _None,
 }
 
 impl InteractibleAction {
-    fn get_barrels() -> Vec<InteractibleAction> {
-        Drink::iterator()
+    fn get_barrels(drinks: &Drinks) -> Vec<InteractibleAction> {
+        drinks
+            .iter()
             .map(|(drink, _)| InteractibleAction::Barrel(drink))
             .collect()
     }
@@ -97,27 +151,48 @@ impl Default for InteractionSpriteColors {
     }
 }
 
+/// Interactible focused with the gamepad or the keyboard. While set, it is highlighted and used instead of the one
+/// under the pointer
+#[derive(Resource, Default)]
+struct InteractibleFocus(Option<Entity>);
+
 #[derive(Component)]
 struct ActiveInteractibleActions(Vec<InteractibleAction>);
 
 #[derive(Component)]
 struct IgnoredInteractibleActions(Vec<InteractibleAction>);
 
-#[derive(Resource)]
-struct DrinkInHand(Option<Drink>);
-
-#[derive(Component)]
-struct InHandText;
-
-#[derive(Resource)]
+#[derive(Resource, Serialize, Deserialize, Clone)]
 #[allow(dead_code)]
 struct PlayerStats {
     pub money: f64,
+    /// Money ever earned from the drink prices
+    pub revenue: f64,
+    /// Money ever earned from tips
+    pub tips: f64,
+    /// Seconds played in the session
+    pub play_time: f32,
     pub streak: u32,
     pub highest_streak: u32,
     pub reputation_level: u32,
     pub reputation_progress: u32,     // 1 exp = 1 customer
-    pub reputation_progress_max: u32, // ToDo fn to get max reputation for current level with a formula
+    pub reputation_progress_max: u32, // Given by ReputationConfig::progress_max for the current level
+}
+
+impl Default for PlayerStats {
+    fn default() -> Self {
+        Self {
+            money: 0.,
+            revenue: 0.,
+            tips: 0.,
+            play_time: 0.,
+            streak: 0,
+            highest_streak: 0,
+            reputation_level: 0,
+            reputation_progress: 0,
+            reputation_progress_max: 10,
+        }
+    }
 }
 
 #[derive(Component)]
@@ -129,27 +204,30 @@ struct StreakText;
 #[derive(Component)]
 struct HighestStreakText;
 
-#[derive(Resource)]
+#[derive(Resource, Serialize, Deserialize, Clone)]
 struct CustomersStats {
     pub customers_wait_duration: f32,
     pub customers_spawn_gap: std::ops::Range<u64>,
 }
 
-// #[derive(Resource)]
-// struct Workday {
-//     pub timer: Timer,
-// }
+impl Default for CustomersStats {
+    fn default() -> Self {
+        Self {
+            customers_wait_duration: 3.,
+            customers_spawn_gap: 0..3,
+        }
+    }
+}
 
-#[derive(Resource)]
+#[derive(Resource, Default, Serialize, Deserialize, Clone, Copy)]
 enum CameraPosition {
     // Zero,
+    #[default]
     OneShelf,
     TwoShelf,
 }
 
 impl CameraPosition {
-    const STARTING_TRANSLATION: Vec3 = Vec3::new(0., -362., 0.);
-
     fn down(&mut self) {
         *self = match *self {
             // CameraPosition::Zero => CameraPosition::OneShelf,
@@ -166,8 +244,8 @@ impl CameraPosition {
         }
     }
 
-    fn to_vec2(&self) -> Vec2 {
-        match *self {
+    fn to_vec2(self) -> Vec2 {
+        match self {
             // CameraPosition::Zero => Vec2::new(0., 0.),
             CameraPosition::OneShelf => Vec2::new(0., -362.),
             CameraPosition::TwoShelf => Vec2::new(0., -717.),
@@ -180,29 +258,36 @@ impl Plugin for IngamePlugin {
     fn build(&self, app: &mut App) {
         app //
             .add_state::<IngameState>()
-            .insert_resource(DrinkInHand(None))
-            .insert_resource(PlayerStats {
-                money: 0.,
-                streak: 0,
-                highest_streak: 0,
-                reputation_level: 0,
-                reputation_progress: 0,
-                reputation_progress_max: 10,
-            })
-            .insert_resource(CustomersStats {
-                customers_wait_duration: 3.,
-                customers_spawn_gap: 0..3,
-            })
-            .insert_resource(CameraPosition::OneShelf)
+            .init_resource::<PlayerStats>()
+            .init_resource::<CustomersStats>()
+            .init_resource::<CameraPosition>()
+            .init_resource::<GameRng>()
+            .init_resource::<InteractibleFocus>()
             .add_plugins(BarPlugin)
             .add_plugins(CustomerPlugin)
+            .add_plugins(SavePlugin)
+            .add_plugins(ReputationPlugin)
+            .add_plugins(WorkdayPlugin)
+            .add_plugins(ShopPlugin)
+            .add_plugins(ModifiersPlugin)
+            .add_plugins(QueuePlugin)
+            .add_plugins(StockPlugin)
+            .add_plugins(PourPlugin)
+            .add_plugins(TrayPlugin)
+            .add_plugins(GlassesPlugin)
+            .add_plugins(TipsPlugin)
+            .add_plugins(GameModePlugin)
+            .add_plugins(GameOverPlugin)
+            .add_plugins(DifficultyPlugin)
             // GameState::Playing // starts with IngameState::Disabled
-            .add_systems(OnEnter(GameState::Playing), (setup_ingame, setup_camera))
+            .add_systems(
+                OnEnter(GameState::Playing),
+                (setup_ingame, setup_camera, setup_pause_button).after(save::start_session),
+            )
             .add_systems(
                 Update,
                 (
                     handle_esc.run_if(in_state(GameState::Playing)),
-                    keys_camera_control.run_if(in_state(GameState::Playing)),
                     update_ui_texts.run_if(in_state(GameState::Playing)),
                 ),
             )
@@ -211,8 +296,12 @@ impl Plugin for IngamePlugin {
             .add_systems(
                 Update,
                 (
-                    interactibles_system.run_if(in_state(IngameState::Running)),
+                    (move_interactible_focus, interactibles_system)
+                        .chain()
+                        .run_if(in_state(IngameState::Running)),
+                    keys_camera_control.run_if(in_state(IngameState::Running)),
                     move_camera_system.run_if(in_state(IngameState::Running)),
+                    handle_button.run_if(in_state(IngameState::Running)),
                 ),
             )
             // IngameState::Paused
@@ -226,13 +315,38 @@ impl Plugin for IngamePlugin {
                 (
                     handle_button.run_if(in_state(IngameState::Settings)),
                     setting_button_handle::<ScreenMode>.run_if(in_state(IngameState::Settings)),
-                    settings_button_colors.run_if(in_state(IngameState::Settings)),
+                    setting_button_handle::<PourMode>.run_if(in_state(IngameState::Settings)),
+                    settings_button_colors::<ScreenMode>.run_if(in_state(IngameState::Settings)),
+                    settings_button_colors::<PourMode>.run_if(in_state(IngameState::Settings)),
+                    setting_button_handle::<Difficulty>.run_if(in_state(IngameState::Settings)),
+                    settings_button_colors::<Difficulty>.run_if(in_state(IngameState::Settings)),
+                    setting_button_handle::<AdaptiveDifficulty>
+                        .run_if(in_state(IngameState::Settings)),
+                    settings_button_colors::<AdaptiveDifficulty>
+                        .run_if(in_state(IngameState::Settings)),
                 ),
             )
             .add_systems(
                 OnExit(IngameState::Settings),
                 despawn_screen::<OnSettingsMenuScreen>,
             )
+            // IngameState::Controls
+            .add_systems(OnEnter(IngameState::Controls), controls_pause_setup)
+            .add_systems(
+                Update,
+                (
+                    handle_button,
+                    handle_binding_buttons,
+                    apply_key_capture,
+                    update_binding_texts,
+                )
+                    .chain()
+                    .run_if(in_state(IngameState::Controls)),
+            )
+            .add_systems(
+                OnExit(IngameState::Controls),
+                (despawn_screen::<OnControlsScreen>, stop_key_capture),
+            )
             // To Main Menu
             .add_systems(OnEnter(IngameState::ToMenu), go_to_main_menu);
     }
@@ -263,7 +377,8 @@ impl CameraBound {
     }
 }
 
-fn setup_camera(mut commands: Commands) {
+fn setup_camera(mut commands: Commands, camera_position: Res<CameraPosition>) {
+    let starting_translation = camera_position.to_vec2().extend(0.);
     // CameraBounds Black Sprites out of screen to hide sprites out of window in weird resolutions.
     // ToDo look for a better solution
     for camera_bound in [
@@ -275,7 +390,7 @@ fn setup_camera(mut commands: Commands) {
         commands
             .spawn(SpriteBundle {
                 transform: Transform {
-                    translation: CameraPosition::STARTING_TRANSLATION + camera_bound.get_offset(),
+                    translation: starting_translation + camera_bound.get_offset(),
                     scale: Vec3::new(CAMERA_RESOLUTION.x, CAMERA_RESOLUTION.y, 0.0),
                     ..Default::default()
                 },
@@ -300,7 +415,7 @@ fn setup_camera(mut commands: Commands) {
     //     bevy::core_pipeline::clear_color::ClearColorConfig::Custom(Color::rgb(0.5, 0.5, 0.5));
     // camera_bundle.camera.hdr = true; // Weir behabior (like a weird effect) with Rgba with high alpha values
 
-    camera_bundle.transform.translation = CameraPosition::STARTING_TRANSLATION;
+    camera_bundle.transform.translation = starting_translation;
 
     commands
         .spawn(camera_bundle)
@@ -314,16 +429,20 @@ fn setup_ingame(
     textures: Res<TextureAssets>,
     mut ingame_state: ResMut<NextState<IngameState>>,
 ) {
-    // ActiveInteractibleActions
-    let mut initial_active_interactibles = vec![InteractibleAction::Customer]; // Add InteractibleAction::Bar to reactivate click transitions
-    initial_active_interactibles.append(&mut InteractibleAction::get_barrels());
+    // ActiveInteractibleActions. The unlocked barrels are added by `bar::update_barrel_locks`
+    // Add InteractibleAction::Bar to reactivate click transitions
+    let initial_active_interactibles = vec![
+        InteractibleAction::Customer,
+        InteractibleAction::Sink,
+        InteractibleAction::DirtyGlass,
+    ];
     commands
         .spawn(ActiveInteractibleActions(initial_active_interactibles))
         .insert(OnIngameScreen);
     // IgnoredInteractibleActions
     #[allow(unused_mut)]
     let mut initial_ignored_interactibles = vec![];
-    // initial_ignored_interactibles.append(&mut InteractibleAction::get_barrels());
+    // initial_ignored_interactibles.append(&mut InteractibleAction::get_barrels(&drinks));
     commands
         .spawn(IgnoredInteractibleActions(initial_ignored_interactibles))
         .insert(OnIngameScreen);
@@ -341,29 +460,7 @@ fn setup_ingame(
         .insert(OnIngameScreen)
         .insert(InteractibleBundle::new(InteractibleAction::ExitBar));
 
-    // InHandText
-    commands
-        .spawn(
-            TextBundle::from_section(
-                "In hand: None",
-                TextStyle {
-                    font_size: 50.,
-                    color: Color::BLACK,
-                    ..Default::default()
-                },
-            )
-            .with_text_alignment(TextAlignment::Center)
-            .with_background_color(Color::rgba(1., 1., 1., 0.1))
-            .with_style(Style {
-                position_type: PositionType::Absolute,
-                left: Val::Px(0.),
-                bottom: Val::Px(0.),
-                padding: UiRect::all(Val::Px(10.)),
-                ..Default::default()
-            }),
-        )
-        .insert(InHandText)
-        .insert(OnIngameScreen);
+    // The tray list is spawned by `tray::setup_tray_hud`
 
     // MoneyText
     commands
@@ -473,10 +570,78 @@ fn move_camera_system(
     }
 }
 
+/// Moves the [`InteractibleFocus`] through the usable interactibles on screen, from left to right, with
+/// `GameAction::NavigateLeft` and `GameAction::NavigateRight`. Moving the mouse drops it.
+fn move_interactible_focus(
+    actions: Res<Actions>,
+    mut interactible_focus: ResMut<InteractibleFocus>,
+    mut cursor_moved_events: EventReader<CursorMoved>,
+    camera_q: Query<
+        (&Camera, &GlobalTransform),
+        (With<MainCameraIngame>, Without<InteractibleAction>),
+    >,
+    interactibles_q: Query<(Entity, &GlobalTransform, &InteractibleAction)>,
+    active_interactibles_q: Query<&ActiveInteractibleActions>,
+    ignored_interactibles_q: Query<&IgnoredInteractibleActions>,
+) {
+    if cursor_moved_events.read().count() > 0 {
+        interactible_focus.0 = None;
+        return;
+    }
+
+    let (camera, camera_global_transform) = camera_q.single();
+    let active_interactibles = active_interactibles_q.single();
+    let ignored_interactibles = ignored_interactibles_q.single();
+    let Some(viewport_rect) = camera.logical_viewport_rect() else {
+        return;
+    };
+
+    let mut focusables = interactibles_q
+        .iter()
+        .filter(|(_, _, interactible_action)| {
+            active_interactibles.0.contains(interactible_action)
+                && !ignored_interactibles.0.contains(interactible_action)
+        })
+        .map(|(entity, global_transform, _)| (entity, global_transform.translation()))
+        .filter(|(_, translation)| {
+            camera
+                .world_to_viewport(camera_global_transform, *translation)
+                .is_some_and(|viewport_position| viewport_rect.contains(viewport_position))
+        })
+        .collect::<Vec<_>>();
+    focusables.sort_by(|(_, a), (_, b)| a.x.total_cmp(&b.x).then(b.y.total_cmp(&a.y)));
+
+    // The focused interactible left the screen, or can't be used anymore
+    let focused_index = interactible_focus
+        .0
+        .and_then(|focused| focusables.iter().position(|(entity, _)| *entity == focused));
+    if focused_index.is_none() {
+        interactible_focus.0 = None;
+    }
+
+    let step = if actions.just_pressed(GameAction::NavigateRight) {
+        1
+    } else if actions.just_pressed(GameAction::NavigateLeft) {
+        -1
+    } else {
+        return;
+    };
+    if focusables.is_empty() {
+        return;
+    }
+    let index = match focused_index {
+        Some(index) => (index as isize + step).rem_euclid(focusables.len() as isize) as usize,
+        None if step > 0 => 0,
+        None => focusables.len() - 1,
+    };
+    interactible_focus.0 = Some(focusables[index].0);
+}
+
 #[allow(clippy::too_many_arguments)]
 fn interactibles_system(
     mut commands: Commands,
-    windows_q: Query<&Window, With<PrimaryWindow>>,
+    actions: Res<Actions>,
+    interactible_focus: Res<InteractibleFocus>,
     camera_q: Query<
         (&Camera, &GlobalTransform),
         (With<MainCameraIngame>, Without<InteractibleAction>),
@@ -492,16 +657,32 @@ fn interactibles_system(
     active_interactibles_q: Query<&ActiveInteractibleActions>,
     ignored_interactibles_q: Query<&IgnoredInteractibleActions>,
     assets: Res<Assets<Image>>,
-    buttons: Res<Input<MouseButton>>,
+    ui_interactions_q: Query<&Interaction>,
 ) {
+    if let Some(focused) = interactible_focus.0 {
+        // Gamepad or keyboard focus, the pointer is ignored
+        for (entity, _, _, _, mut interactible_sprite, interaction_sprite_colors) in
+            interactibles_q.iter_mut()
+        {
+            interactible_sprite.color = if entity == focused {
+                interaction_sprite_colors.highlight
+            } else {
+                interaction_sprite_colors.normal
+            };
+        }
+        if actions.just_pressed(GameAction::Activate) {
+            commands.entity(focused).insert(ClickedInteractible);
+        }
+        return;
+    }
+
     let (camera, camera_global_transform) = camera_q.single();
 
-    if let Some(cursor_world_position) = windows_q
-        .single()
-        .cursor_position()
-        .and_then(|cursor| camera.viewport_to_world_2d(camera_global_transform, cursor))
+    if let Some(cursor_world_position) = actions
+        .pointer()
+        .and_then(|pointer| camera.viewport_to_world_2d(camera_global_transform, pointer))
     {
-        // Cursor is inside the primary window, at 'world_position'
+        // Cursor (or touch) is inside the primary window, at 'world_position'
 
         // Active Interactibles
         let active_interactibles = active_interactibles_q.single();
@@ -556,9 +737,12 @@ fn interactibles_system(
                 // Highlight
                 interactible_sprite.color = interaction_sprite_colors.highlight;
 
-                // Handle mouse click
-                if buttons.just_pressed(MouseButton::Left) {
-                    // Left button was pressed
+                // Handle click or tap, unless it is on a HUD button
+                if actions.just_pressed(GameAction::Interact)
+                    && ui_interactions_q
+                        .iter()
+                        .all(|interaction| *interaction == Interaction::None)
+                {
                     commands.entity(entity).insert(ClickedInteractible);
                 }
             } else {
@@ -583,20 +767,10 @@ fn interactibles_system(
 }
 
 fn update_ui_texts(
-    mut q_in_hand_text: Query<
-        &mut Text,
-        (
-            With<InHandText>,
-            Without<MoneyText>,
-            Without<StreakText>,
-            Without<HighestStreakText>,
-        ),
-    >,
     mut q_money_text: Query<
         &mut Text,
         (
             With<MoneyText>,
-            Without<InHandText>,
             Without<StreakText>,
             Without<HighestStreakText>,
         ),
@@ -605,7 +779,6 @@ fn update_ui_texts(
         &mut Text,
         (
             With<StreakText>,
-            Without<InHandText>,
             Without<MoneyText>,
             Without<HighestStreakText>,
         ),
@@ -614,23 +787,14 @@ fn update_ui_texts(
         &mut Text,
         (
             With<HighestStreakText>,
-            Without<InHandText>,
             Without<MoneyText>,
             Without<StreakText>,
         ),
     >,
-    drink_in_hand: Res<DrinkInHand>,
     player_stats: Res<PlayerStats>,
 ) {
-    let mut in_hand_text = q_in_hand_text.single_mut();
-    if let Some(drink) = drink_in_hand.0 {
-        in_hand_text.sections[0].value = format!("In hand: {}", drink);
-    } else {
-        in_hand_text.sections[0].value = "In hand: None".to_string();
-    }
-
     let mut money_text = q_money_text.single_mut();
-    money_text.sections[0].value = format!("Money: {}", player_stats.money);
+    money_text.sections[0].value = format!("Money: {:.2}", player_stats.money);
 
     let mut streak_text = q_streak_text.single_mut();
     streak_text.sections[0].value = format!("Streak: {}", player_stats.streak);
@@ -641,8 +805,7 @@ fn update_ui_texts(
 }
 
 fn keys_camera_control(
-    keys: ResMut<Input<KeyCode>>,
-    mut scroll_evr: EventReader<MouseWheel>,
+    actions: Res<Actions>,
     mut move_camera_to_q: Query<
         &mut MoveCameraTo,
         (With<MainCameraIngame>, Without<InteractibleAction>),
@@ -651,39 +814,31 @@ fn keys_camera_control(
 ) {
     let mut move_camera_to = move_camera_to_q.single_mut();
 
-    if keys.just_pressed(KeyCode::W) || keys.just_pressed(KeyCode::Up) {
+    if actions.just_pressed(GameAction::ShelfUp) {
         camera_position.up();
         move_camera_to.0 = Some(camera_position.to_vec2());
     }
-    if keys.just_pressed(KeyCode::S) || keys.just_pressed(KeyCode::Down) {
+    if actions.just_pressed(GameAction::ShelfDown) {
         camera_position.down();
         move_camera_to.0 = Some(camera_position.to_vec2());
     }
-
-    if let Some(scroll) = scroll_evr.read().last() {
-        if scroll.y < 0. {
-            camera_position.down();
-            move_camera_to.0 = Some(camera_position.to_vec2());
-        } else if scroll.y > 0. {
-            camera_position.up();
-            move_camera_to.0 = Some(camera_position.to_vec2());
-        }
-    }
 }
 
 fn handle_esc(
-    mut keys: ResMut<Input<KeyCode>>,
+    mut actions: ResMut<Actions>,
     ingame_state: Res<State<IngameState>>,
     mut ingame_next_state: ResMut<NextState<IngameState>>,
+    shop_origin: Res<ShopOrigin>,
 ) {
-    if keys.just_pressed(KeyCode::Escape) {
-        keys.reset(KeyCode::Escape);
-        match *ingame_state.get() {
-            IngameState::Running => ingame_next_state.set(IngameState::Paused),
-            IngameState::Paused => ingame_next_state.set(IngameState::Running),
-            IngameState::Settings => ingame_next_state.set(IngameState::Paused),
-            _ => {}
-        }
+    let pause = actions.consume(GameAction::Pause);
+    let back = actions.consume(GameAction::Back);
+    match *ingame_state.get() {
+        IngameState::Running if pause => ingame_next_state.set(IngameState::Paused),
+        IngameState::Paused if pause || back => ingame_next_state.set(IngameState::Running),
+        IngameState::Settings if back => ingame_next_state.set(IngameState::Paused),
+        IngameState::Controls if back => ingame_next_state.set(IngameState::Settings),
+        IngameState::Shop if back => ingame_next_state.set(shop_origin.0),
+        _ => {}
     }
 }
 
@@ -0,0 +1,111 @@
+//! Modifier layer between the base game values and the gameplay systems.
+//!
+//! The [`Modifiers`] resource is rebuilt from the purchased `Upgrades` whenever they change. Gameplay systems
+//! apply it on top of the base values (like `CustomersStats` or the drink prices of the catalogue) instead of
+//! the upgrades mutating those values directly.
+
+use bevy::prelude::*;
+
+use crate::drinks::{Drink, Drinks};
+
+use super::shop::Upgrades;
+
+pub struct ModifiersPlugin;
+
+impl Plugin for ModifiersPlugin {
+    fn build(&self, app: &mut App) {
+        app //
+            .init_resource::<Modifiers>()
+            .add_systems(
+                PreUpdate,
+                update_modifiers.run_if(resource_changed::<Upgrades>()),
+            );
+    }
+}
+
+#[derive(Resource)]
+pub struct Modifiers {
+    /// Multiplies `CustomersStats.customers_wait_duration`
+    pub patience_multiplier: f32,
+    /// Multiplies the price of every drink
+    pub price_multiplier: f64,
+    /// Drinks available regardless of the reputation level
+    pub extra_drinks: Vec<Drink>,
+    /// Added to `TrayConfig.base_capacity`
+    pub extra_tray_capacity: usize,
+    /// Added to `BarConfig.slot_count`
+    pub extra_seats: usize,
+    /// Multiplies `PourConfig.fill_speed`
+    pub pour_speed_multiplier: f32,
+}
+
+impl Default for Modifiers {
+    fn default() -> Self {
+        Self {
+            patience_multiplier: 1.,
+            price_multiplier: 1.,
+            extra_drinks: Vec::new(),
+            extra_tray_capacity: 0,
+            extra_seats: 0,
+            pour_speed_multiplier: 1.,
+        }
+    }
+}
+
+impl From<&Upgrades> for Modifiers {
+    fn from(upgrades: &Upgrades) -> Self {
+        Self {
+            patience_multiplier: 1. + 0.15 * upgrades.patience as f32,
+            price_multiplier: 1. + 0.1 * upgrades.drink_prices as f64,
+            extra_drinks: upgrades.barrels.clone(),
+            extra_tray_capacity: upgrades.tray as usize,
+            extra_seats: upgrades.seats as usize,
+            pour_speed_multiplier: 1. + 0.2 * upgrades.pour_speed as f32,
+        }
+    }
+}
+
+impl Modifiers {
+    pub fn drink_price(&self, drinks: &Drinks, drink: Drink) -> f64 {
+        drinks.get(drink).price * self.price_multiplier
+    }
+
+    pub fn wait_duration(&self, base_wait_duration: f32) -> f32 {
+        base_wait_duration * self.patience_multiplier
+    }
+
+    pub fn tray_capacity(&self, base_capacity: usize) -> usize {
+        base_capacity + self.extra_tray_capacity
+    }
+
+    pub fn slot_count(&self, base_slot_count: usize) -> usize {
+        base_slot_count + self.extra_seats
+    }
+
+    pub fn fill_speed(&self, base_fill_speed: f32) -> f32 {
+        base_fill_speed * self.pour_speed_multiplier
+    }
+
+    /// The cheapest drink is always unlocked, so that customers have something to order even if the catalogue
+    /// locks every drink behind the reputation
+    pub fn is_drink_unlocked(&self, drinks: &Drinks, drink: Drink, reputation_level: u32) -> bool {
+        drinks.get(drink).is_unlocked(reputation_level)
+            || self.extra_drinks.contains(&drink)
+            || drinks.cheapest() == Some(drink)
+    }
+
+    pub fn unlocked_drinks<'a>(
+        &'a self,
+        drinks: &'a Drinks,
+        reputation_level: u32,
+    ) -> impl Iterator<Item = Drink> + 'a {
+        drinks
+            .iter()
+            .map(|(drink, _)| drink)
+            .filter(move |drink| self.is_drink_unlocked(drinks, *drink, reputation_level))
+    }
+}
+
+fn update_modifiers(upgrades: Res<Upgrades>, mut modifiers: ResMut<Modifiers>) {
+    *modifiers = Modifiers::from(&*upgrades);
+}
@@ -0,0 +1,269 @@
+//! Customer orders.
+//!
+//! An [`Order`] is a ticket of one or more drinks, each with a quantity. Every correct drink handed to the
+//! customer is ticked off the ticket, and the customer only pays once the whole order is delivered.
+//! Handing a drink that is not on the ticket is penalized following the [`WrongDrinkConfig`].
+
+use bevy::prelude::*;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::{archetypes::ArchetypeDefinition, drinks::Drink, drinks::Drinks, Difficulty};
+
+use super::pour::PourQuality;
+
+/// Size of the orders.
+#[derive(Resource)]
+pub struct OrderConfig {
+    /// Most drinks in a single order. Orders can have one more drink per reputation level, up to this
+    pub max_drinks: u32,
+    /// Chance of an order being a single drink, when bigger orders are possible
+    pub single_drink_chance: f64,
+    /// Every drink after the first gives the customer this fraction of its wait duration in extra time
+    pub extra_wait_per_drink: f32,
+}
+
+impl Default for OrderConfig {
+    fn default() -> Self {
+        Self {
+            max_drinks: 4,
+            single_drink_chance: 0.6,
+            extra_wait_per_drink: 0.5,
+        }
+    }
+}
+
+/// What happens when a customer is handed a drink that is not on their order.
+pub struct WrongDrinkRules {
+    /// Fraction of `PlayerStats.streak` kept, 0 resets it
+    pub streak_kept: f32,
+    /// Fraction of the remaining wait time the customer loses
+    pub wait_cut: f32,
+}
+
+/// [`WrongDrinkRules`] for each difficulty.
+#[derive(Resource)]
+pub struct WrongDrinkConfig {
+    pub easy: WrongDrinkRules,
+    pub normal: WrongDrinkRules,
+    pub hard: WrongDrinkRules,
+}
+
+impl Default for WrongDrinkConfig {
+    fn default() -> Self {
+        Self {
+            easy: WrongDrinkRules {
+                streak_kept: 0.5,
+                wait_cut: 0.1,
+            },
+            normal: WrongDrinkRules {
+                streak_kept: 0.,
+                wait_cut: 0.25,
+            },
+            hard: WrongDrinkRules {
+                streak_kept: 0.,
+                wait_cut: 0.5,
+            },
+        }
+    }
+}
+
+impl WrongDrinkConfig {
+    pub fn rules(&self, difficulty: Difficulty) -> &WrongDrinkRules {
+        match difficulty {
+            Difficulty::Easy => &self.easy,
+            Difficulty::Normal => &self.normal,
+            Difficulty::Hard => &self.hard,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OrderItem {
+    pub drink: Drink,
+    pub quantity: u32,
+    pub delivered: u32,
+}
+
+impl OrderItem {
+    fn is_done(&self) -> bool {
+        self.delivered >= self.quantity
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Order {
+    items: Vec<OrderItem>,
+    /// Quality of every drink delivered so far
+    qualities: Vec<PourQuality>,
+}
+
+impl Order {
+    /// Random order of the `unlocked_drinks`, following the drink preferences of the archetype
+    pub fn random<R: Rng>(
+        order_config: &OrderConfig,
+        archetype: &ArchetypeDefinition,
+        unlocked_drinks: &[Drink],
+        reputation_level: u32,
+        rng: &mut R,
+    ) -> Option<Self> {
+        let max_drinks = order_config.max_drinks.min(reputation_level + 1).max(1);
+        let drink_count = if max_drinks == 1 || rng.gen_bool(order_config.single_drink_chance) {
+            1
+        } else {
+            rng.gen_range(2..=max_drinks)
+        };
+
+        let mut order = Order {
+            items: Vec::new(),
+            qualities: Vec::new(),
+        };
+        for _ in 0..drink_count {
+            let drink = archetype.choose_drink(unlocked_drinks, rng)?;
+            match order.items.iter_mut().find(|item| item.drink == drink) {
+                Some(item) => item.quantity += 1,
+                None => order.items.push(OrderItem {
+                    drink,
+                    quantity: 1,
+                    delivered: 0,
+                }),
+            }
+        }
+        Some(order)
+    }
+
+    pub fn items(&self) -> impl Iterator<Item = &OrderItem> {
+        self.items.iter()
+    }
+
+    /// True if every drink of the order is in the catalogue (used when restoring a save)
+    pub fn is_valid(&self, drinks: &Drinks) -> bool {
+        self.items.iter().all(|item| drinks.contains(item.drink))
+    }
+
+    /// Total number of drinks ordered
+    pub fn drink_count(&self) -> u32 {
+        self.items.iter().map(|item| item.quantity).sum()
+    }
+
+    /// Number of drinks handed to the customer so far
+    pub fn delivered_count(&self) -> u32 {
+        self.items.iter().map(|item| item.delivered).sum()
+    }
+
+    /// Ticks the drink off the ticket. Returns false if the drink was not ordered or is already delivered.
+    pub fn deliver(&mut self, drink: Drink, quality: PourQuality) -> bool {
+        match self
+            .items
+            .iter_mut()
+            .find(|item| item.drink == drink && !item.is_done())
+        {
+            Some(item) => {
+                item.delivered += 1;
+                self.qualities.push(quality);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Average [`PourQuality::tip_factor`] of the drinks delivered
+    pub fn tip_factor(&self) -> f64 {
+        if self.qualities.is_empty() {
+            return 1.;
+        }
+        self.qualities
+            .iter()
+            .map(PourQuality::tip_factor)
+            .sum::<f64>()
+            / self.qualities.len() as f64
+    }
+
+    pub fn is_fulfilled(&self) -> bool {
+        self.items.iter().all(OrderItem::is_done)
+    }
+
+    /// Lines of the order ticket, with whether each one is already delivered
+    pub fn ticket(&self, drinks: &Drinks) -> Vec<(String, bool)> {
+        self.items
+            .iter()
+            .map(|item| {
+                let name = &drinks.get(item.drink).name;
+                let line = if item.quantity == 1 {
+                    name.clone()
+                } else {
+                    format!("{}/{} {}", item.delivered, item.quantity, name)
+                };
+                (line, item.is_done())
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Beer and wine, and an order of two beers and a wine
+    fn beer_wine_order() -> (Drink, Drink, Order) {
+        let drinks = Drinks::from_prices(&[("Beer", 2.), ("Wine", 5.)]);
+        let mut drinks = drinks.iter().map(|(drink, _)| drink);
+        let (beer, wine) = (drinks.next().unwrap(), drinks.next().unwrap());
+        let order = Order {
+            items: vec![
+                OrderItem {
+                    drink: beer,
+                    quantity: 2,
+                    delivered: 0,
+                },
+                OrderItem {
+                    drink: wine,
+                    quantity: 1,
+                    delivered: 0,
+                },
+            ],
+            qualities: Vec::new(),
+        };
+        (beer, wine, order)
+    }
+
+    #[test]
+    fn deliver_ticks_off_the_order() {
+        let (beer, wine, mut order) = beer_wine_order();
+        assert_eq!(order.drink_count(), 3);
+
+        assert!(order.deliver(beer, PourQuality::Instant));
+        assert!(!order.is_fulfilled());
+        assert_eq!(order.delivered_count(), 1);
+        assert!(order.deliver(wine, PourQuality::Instant));
+        assert!(order.deliver(beer, PourQuality::Instant));
+        assert!(order.is_fulfilled());
+    }
+
+    #[test]
+    fn deliver_refuses_extra_drinks() {
+        let (_, wine, mut order) = beer_wine_order();
+        assert!(order.deliver(wine, PourQuality::Instant));
+        assert!(!order.deliver(wine, PourQuality::Instant));
+        assert_eq!(order.items().map(|item| item.delivered).sum::<u32>(), 1);
+    }
+
+    #[test]
+    fn deliver_refuses_drinks_not_ordered() {
+        let drinks = Drinks::from_prices(&[("Beer", 2.), ("Wine", 5.), ("Whiskey", 11.)]);
+        let whiskey = drinks.iter().map(|(drink, _)| drink).last().unwrap();
+        let (_, _, mut order) = beer_wine_order();
+        assert!(!order.deliver(whiskey, PourQuality::Perfect));
+        assert_eq!(order.tip_factor(), 1.);
+    }
+
+    #[test]
+    fn tip_factor_averages_the_delivered_qualities() {
+        let (beer, wine, mut order) = beer_wine_order();
+        order.deliver(beer, PourQuality::Perfect);
+        order.deliver(wine, PourQuality::Underfilled);
+        let expected =
+            (PourQuality::Perfect.tip_factor() + PourQuality::Underfilled.tip_factor()) / 2.;
+        assert!((order.tip_factor() - expected).abs() < 1e-9);
+    }
+}
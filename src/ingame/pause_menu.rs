@@ -1,27 +1,70 @@
 use bevy::prelude::*;
 
 use crate::{
+    drinks::Drinks,
     menu::{
+        controls::{spawn_controls_page, OnControlsScreen},
         menu_button,
-        settings::{OnSettingsMenuScreen, SelectedOption},
+        settings::{spawn_setting_row, OnSettingsMenuScreen},
         ButtonColors,
     },
-    ScreenMode, TEXT_COLOR,
+    AdaptiveDifficulty, Difficulty, PourMode, ScreenMode, TEXT_COLOR,
 };
 
-use super::{IngameState, MainCameraIngame, CAMERA_RESOLUTION};
+use super::{
+    bar::BarConfig, shop::ShopOrigin, IngameState, MainCameraIngame, OnIngameScreen,
+    CAMERA_RESOLUTION,
+};
 
 #[derive(Component)]
 pub struct OnPauseMenu;
 
 #[derive(Component)]
 pub enum PauseButtonAction {
+    Pause,
     Resume,
+    Shop,
     Settings,
+    Controls,
     BackToPaused,
     MainMenu(bool),
 }
 
+/// On-screen pause button of the HUD, for touch screens where there is no Escape key
+pub fn setup_pause_button(mut commands: Commands) {
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    right: Val::Px(10.),
+                    // Below the highest streak
+                    top: Val::Px(80.),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            OnIngameScreen,
+        ))
+        .with_children(|child_builder| {
+            menu_button(
+                child_builder,
+                "Pause",
+                PauseButtonAction::Pause,
+                &Style {
+                    padding: UiRect::axes(Val::Px(20.), Val::Px(10.)),
+                    ..Default::default()
+                },
+                &ButtonColors::default(),
+                &TextStyle {
+                    font_size: 40.,
+                    color: TEXT_COLOR,
+                    ..Default::default()
+                },
+            );
+        });
+}
+
 pub fn setup_pause_menu(
     mut commands: Commands,
     camera_q: Query<&Transform, With<MainCameraIngame>>,
@@ -138,6 +181,15 @@ pub fn setup_pause_menu(
                 &button_text_style,
             );
 
+            menu_button(
+                child_builder,
+                "Shop",
+                PauseButtonAction::Shop,
+                &button_style,
+                &ButtonColors::default(),
+                &button_text_style,
+            );
+
             menu_button(
                 child_builder,
                 "Settings",
@@ -161,11 +213,12 @@ pub fn setup_pause_menu(
         });
 }
 
-const NORMAL_BUTTON: Color = Color::rgb(0.15, 0.15, 0.15);
-
 pub fn settings_pause_setup(
     mut commands: Commands,
     screen_mode: Res<ScreenMode>,
+    pour_mode: Res<PourMode>,
+    difficulty: Res<Difficulty>,
+    adaptive_difficulty: Res<AdaptiveDifficulty>,
     camera_q: Query<&Transform, (With<Camera>, With<MainCameraIngame>)>,
 ) {
     // Transparent Pause background
@@ -257,57 +310,50 @@ pub fn settings_pause_setup(
                 ..Default::default()
             };
 
-            child_builder
-                .spawn(NodeBundle {
-                    style: Style {
-                        align_items: AlignItems::Center,
-                        column_gap: Val::Px(15.),
-                        ..default()
-                    },
-                    ..default()
-                })
-                .with_children(|child_builder| {
-                    // Display a label for the current setting
-                    child_builder.spawn(
-                        TextBundle::from_section("Window Mode:", button_text_style.clone())
-                            .with_text_alignment(TextAlignment::Center)
-                            .with_style(Style {
-                                // width: Val::Px(300.0),
-                                // height: Val::Px(50.0),
-                                justify_content: JustifyContent::Center,
-                                align_items: AlignItems::Center,
-                                margin: UiRect {
-                                    right: Val::Px(35.),
-                                    bottom: Val::Px(25.),
-                                    ..Default::default()
-                                },
-                                // padding: UiRect::axes(Val::Px(15.), Val::Px(10.)),
-                                ..Default::default()
-                            }),
-                    );
-                    // Display a button for each possible value
-                    for screen_mode_setting in
-                        [ScreenMode::BorderlessFullscreen, ScreenMode::Windowed]
-                    {
-                        let mut entity = child_builder.spawn((
-                            ButtonBundle {
-                                style: button_style.clone(),
-                                background_color: NORMAL_BUTTON.into(),
-                                ..default()
-                            },
-                            screen_mode_setting,
-                        ));
-                        entity.with_children(|child_builder| {
-                            child_builder.spawn(TextBundle::from_section(
-                                format!("{screen_mode_setting:?}"),
-                                button_text_style.clone(),
-                            ));
-                        });
-                        if *screen_mode == screen_mode_setting {
-                            entity.insert(SelectedOption);
-                        }
-                    }
-                });
+            spawn_setting_row(
+                child_builder,
+                "Window Mode:",
+                &[ScreenMode::BorderlessFullscreen, ScreenMode::Windowed],
+                *screen_mode,
+                &button_style,
+                &button_text_style,
+            );
+            spawn_setting_row(
+                child_builder,
+                "Pouring:",
+                &[PourMode::Pour, PourMode::Instant],
+                *pour_mode,
+                &button_style,
+                &button_text_style,
+            );
+            spawn_setting_row(
+                child_builder,
+                "Difficulty:",
+                &[Difficulty::Easy, Difficulty::Normal, Difficulty::Hard],
+                *difficulty,
+                &button_style,
+                &button_text_style,
+            );
+            spawn_setting_row(
+                child_builder,
+                "Adaptive:",
+                &[AdaptiveDifficulty::Off, AdaptiveDifficulty::On],
+                *adaptive_difficulty,
+                &button_style,
+                &button_text_style,
+            );
+
+            menu_button(
+                child_builder,
+                "Controls",
+                PauseButtonAction::Controls,
+                &Style {
+                    margin: UiRect::top(Val::Px(25.)),
+                    ..button_style.clone()
+                },
+                &ButtonColors::default(),
+                &button_text_style,
+            );
 
             menu_button(
                 child_builder,
@@ -328,6 +374,42 @@ pub fn settings_pause_setup(
         });
 }
 
+pub fn controls_pause_setup(
+    mut commands: Commands,
+    camera_q: Query<&Transform, (With<Camera>, With<MainCameraIngame>)>,
+    drinks: Res<Drinks>,
+    bar_config: Res<BarConfig>,
+) {
+    // Transparent Pause background
+    let camera_transform = camera_q.single();
+    commands
+        .spawn(SpriteBundle {
+            transform: Transform {
+                translation: camera_transform.translation.xy().extend(111.),
+                scale: CAMERA_RESOLUTION.extend(0.),
+                ..Default::default()
+            },
+            sprite: Sprite {
+                color: Color::Rgba {
+                    red: 0.,
+                    green: 0.,
+                    blue: 0.,
+                    alpha: 0.98,
+                },
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+        .insert(OnControlsScreen);
+
+    spawn_controls_page(
+        &mut commands,
+        PauseButtonAction::Settings,
+        &drinks,
+        &bar_config,
+    );
+}
+
 pub fn handle_button(
     mut ingame_state: ResMut<NextState<IngameState>>,
     mut interaction_query: Query<
@@ -339,14 +421,21 @@ pub fn handle_button(
         ),
         (Changed<Interaction>, With<Button>),
     >,
+    mut shop_origin: ResMut<ShopOrigin>,
 ) {
     for (interaction, mut color, mut button_colors, pause_button_action) in &mut interaction_query {
         match *interaction {
             Interaction::Pressed => {
                 if let Some(mut action) = pause_button_action {
                     match *action {
+                        PauseButtonAction::Pause => ingame_state.set(IngameState::Paused),
                         PauseButtonAction::Resume => ingame_state.set(IngameState::Running),
+                        PauseButtonAction::Shop => {
+                            shop_origin.0 = IngameState::Paused;
+                            ingame_state.set(IngameState::Shop);
+                        }
                         PauseButtonAction::Settings => ingame_state.set(IngameState::Settings),
+                        PauseButtonAction::Controls => ingame_state.set(IngameState::Controls),
                         PauseButtonAction::BackToPaused => ingame_state.set(IngameState::Paused),
                         PauseButtonAction::MainMenu(confirm) => {
                             if !confirm {
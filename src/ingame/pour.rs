@@ -0,0 +1,222 @@
+//! Pouring minigame.
+//!
+//! With `PourMode::Pour`, clicking a barrel puts a glass above it that fills while `GameAction::Interact` is held
+//! (the mouse button or a touch), or `GameAction::Activate` (the gamepad South button) or the hotkey of the barrel.
+//! The glass waits for the first hold, so a tap on a touch screen pours with the next touch. Releasing it puts the
+//! drink on the tray with a [`PourQuality`] given by how full the glass is, which multiplies the tip of the order.
+//! With `PourMode::Instant` the drink goes on the tray right away.
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::actions::{Actions, GameAction};
+use crate::drinks::{Drink, Drinks};
+
+use super::{modifiers::Modifiers, tray::Tray, IngameState, OnIngameScreen};
+
+pub struct PourPlugin;
+
+impl Plugin for PourPlugin {
+    fn build(&self, app: &mut App) {
+        app //
+            .init_resource::<PourConfig>()
+            .add_systems(Update, pour_system.run_if(in_state(IngameState::Running)));
+    }
+}
+
+// Constants
+const GLASS_SIZE: Vec2 = Vec2::new(70., 110.);
+const GLASS_BORDER: f32 = 5.;
+/// Position of the glass relative to its barrel
+const GLASS_OFFSET: Vec3 = Vec3::new(0., 220., 5.);
+const GLASS_FILL_COLOR: Color = Color::rgb(0.95, 0.7, 0.2);
+const GLASS_OVERFLOW_COLOR: Color = Color::rgb(0.95, 0.95, 0.85);
+
+/// Speed of the pour and fill needed for each [`PourQuality`].
+#[derive(Resource)]
+pub struct PourConfig {
+    /// Fraction of the glass filled per second, before the pour speed upgrades
+    pub fill_speed: f32,
+    /// Glasses filled at least this much are perfect, up to completely full
+    pub perfect_fill: f32,
+    /// The pour stops by itself once the glass is this full
+    pub overflow_limit: f32,
+}
+
+impl Default for PourConfig {
+    fn default() -> Self {
+        Self {
+            fill_speed: 0.7,
+            perfect_fill: 0.85,
+            overflow_limit: 1.3,
+        }
+    }
+}
+
+impl PourConfig {
+    fn quality(&self, fill: f32) -> PourQuality {
+        if fill < self.perfect_fill {
+            PourQuality::Underfilled
+        } else if fill <= 1. {
+            PourQuality::Perfect
+        } else {
+            PourQuality::Overflowing
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PourQuality {
+    Underfilled,
+    Perfect,
+    Overflowing,
+    /// Taken without the minigame (`PourMode::Instant`)
+    Instant,
+}
+
+impl PourQuality {
+    /// Multiplies the tip of the order
+    pub fn tip_factor(&self) -> f64 {
+        match self {
+            PourQuality::Underfilled => 0.7,
+            PourQuality::Perfect => 1.25,
+            PourQuality::Overflowing => 0.85,
+            PourQuality::Instant => 1.,
+        }
+    }
+}
+
+/// A drink ready to be served
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PouredDrink {
+    pub drink: Drink,
+    pub quality: PourQuality,
+}
+
+/// Glass being filled. There is at most one at a time
+#[derive(Component)]
+pub struct PouringGlass {
+    drink: Drink,
+    /// 1 is a full glass
+    fill: f32,
+    /// Set once the pour input is held. The drink goes on the tray when it is released
+    filling: bool,
+}
+
+impl PouringGlass {
+    /// True while an input pouring into the glass is held
+    fn pour_held(&self, actions: &Actions, drinks: &Drinks) -> bool {
+        actions.pressed(GameAction::Interact)
+            || actions.pressed(GameAction::Activate)
+            || drinks
+                .iter()
+                .position(|(drink, _)| drink == self.drink)
+                .is_some_and(|index| actions.pressed(GameAction::SelectBarrel(index)))
+    }
+
+    /// The drink as if the pour stopped now
+    pub fn poured(&self, pour_config: &PourConfig) -> PouredDrink {
+        PouredDrink {
+            drink: self.drink,
+            quality: pour_config.quality(self.fill),
+        }
+    }
+}
+
+#[derive(Component)]
+struct GlassFill;
+
+/// Puts an empty glass above the barrel at `barrel_position` and starts pouring `drink`
+pub fn start_pour(
+    commands: &mut Commands,
+    pour_config: &PourConfig,
+    drink: Drink,
+    barrel_position: Vec3,
+) {
+    commands
+        .spawn(SpriteBundle {
+            sprite: Sprite {
+                color: Color::BLACK,
+                custom_size: Some(GLASS_SIZE + Vec2::splat(GLASS_BORDER * 2.)),
+                ..Default::default()
+            },
+            transform: Transform::from_translation(barrel_position + GLASS_OFFSET),
+            ..Default::default()
+        })
+        .insert(PouringGlass {
+            drink,
+            fill: 0.,
+            filling: false,
+        })
+        .insert(OnIngameScreen)
+        .with_children(|child_builder| {
+            child_builder
+                .spawn(SpriteBundle {
+                    sprite: Sprite {
+                        color: GLASS_FILL_COLOR,
+                        custom_size: Some(Vec2::new(GLASS_SIZE.x, 0.)),
+                        anchor: bevy::sprite::Anchor::BottomCenter,
+                        ..Default::default()
+                    },
+                    transform: Transform::from_xyz(0., -GLASS_SIZE.y / 2., 0.1),
+                    ..Default::default()
+                })
+                .insert(GlassFill);
+            // Marks where the perfect pour starts
+            child_builder.spawn(SpriteBundle {
+                sprite: Sprite {
+                    color: Color::WHITE,
+                    custom_size: Some(Vec2::new(GLASS_SIZE.x + GLASS_BORDER * 4., 3.)),
+                    ..Default::default()
+                },
+                transform: Transform::from_xyz(
+                    0.,
+                    GLASS_SIZE.y * (pour_config.perfect_fill - 0.5),
+                    0.2,
+                ),
+                ..Default::default()
+            });
+        });
+}
+
+/// Fills the glass while the input is held, and puts the drink on the tray once released
+#[allow(clippy::too_many_arguments)]
+fn pour_system(
+    mut commands: Commands,
+    time: Res<Time>,
+    pour_config: Res<PourConfig>,
+    modifiers: Res<Modifiers>,
+    actions: Res<Actions>,
+    drinks: Res<Drinks>,
+    mut tray: ResMut<Tray>,
+    mut glasses_q: Query<(Entity, &mut PouringGlass, &Children)>,
+    mut glass_fills_q: Query<&mut Sprite, With<GlassFill>>,
+) {
+    for (entity, mut glass, children) in glasses_q.iter_mut() {
+        let held = glass.pour_held(&actions, &drinks);
+        if !held && !glass.filling {
+            continue;
+        }
+        glass.filling = true;
+        if held && glass.fill < pour_config.overflow_limit {
+            glass.fill = (glass.fill
+                + modifiers.fill_speed(pour_config.fill_speed) * time.delta_seconds())
+            .min(pour_config.overflow_limit);
+
+            for child in children.iter() {
+                if let Ok(mut sprite) = glass_fills_q.get_mut(*child) {
+                    sprite.custom_size =
+                        Some(Vec2::new(GLASS_SIZE.x, GLASS_SIZE.y * glass.fill.min(1.)));
+                    sprite.color = if glass.fill > 1. {
+                        GLASS_OVERFLOW_COLOR
+                    } else {
+                        GLASS_FILL_COLOR
+                    };
+                }
+            }
+        } else {
+            tray.push(glass.poured(&pour_config));
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
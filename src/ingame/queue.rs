@@ -0,0 +1,235 @@
+//! Queue of customers waiting at the tavern door.
+//!
+//! When every slot of the bar is taken, new customers line up at the door instead. The first one in line takes
+//! the next free slot. Customers in the queue have their own patience: the ones that give up walk out and cost
+//! reputation (see [`QueueConfig`]).
+
+use bevy::prelude::*;
+
+use crate::ScaleByAssetResolution;
+
+use super::{
+    bar::Bar,
+    customer::{CustomerBundle, CustomerLost},
+    game_mode::CurrentRules,
+    rng::GameRng,
+    save::SavedQueuedCustomer,
+    workday::DayStats,
+    IngameState, OnIngameScreen, PlayerStats,
+};
+
+pub struct QueuePlugin;
+
+impl Plugin for QueuePlugin {
+    fn build(&self, app: &mut App) {
+        app //
+            .init_resource::<QueueConfig>()
+            .add_systems(
+                Update,
+                (
+                    tick_queue_patience,
+                    seat_queued_customers,
+                    move_queued_customers,
+                )
+                    .chain()
+                    .run_if(in_state(IngameState::Running)),
+            );
+    }
+}
+
+// Constants
+/// Where customers come in and walk out, in front of the door of the background
+const QUEUE_DOOR_POINT: Vec3 = Vec3::new(180., -110., -5.);
+/// Position of the first customer in line. The next ones stand further back, towards the door
+const QUEUE_FRONT_POINT: Vec3 = Vec3::new(330., -430., -1.);
+const QUEUE_PLACE_OFFSET: Vec3 = Vec3::new(-35., 60., -0.1);
+/// Scale of the first customer in line, relative to the customers at the bar
+const QUEUE_FRONT_SCALE: f32 = 0.4;
+const QUEUE_WALK_SPEED: f32 = 300.;
+
+/// Size of the queue and patience of the customers in it.
+#[derive(Resource)]
+pub struct QueueConfig {
+    /// No more customers come in while this many are waiting
+    pub max_length: usize,
+    /// Seconds a customer waits in the queue before giving up
+    pub patience: f32,
+    /// Reputation progress lost for every customer giving up
+    pub reputation_penalty: u32,
+}
+
+impl Default for QueueConfig {
+    fn default() -> Self {
+        Self {
+            max_length: 4,
+            patience: 12.,
+            reputation_penalty: 2,
+        }
+    }
+}
+
+#[derive(Component)]
+pub struct QueuedCustomer {
+    /// Taken when the customer gets a slot at the bar
+    customer: Option<CustomerBundle>,
+    patience: Timer,
+    /// Position in the line, 0 being the first
+    place: usize,
+    leaving: bool,
+}
+
+impl QueuedCustomer {
+    /// True if the customer is still in line, and not walking out
+    pub fn is_waiting(&self) -> bool {
+        !self.leaving
+    }
+
+    /// None if the customer is walking out
+    pub fn to_saved(&self) -> Option<SavedQueuedCustomer> {
+        self.customer
+            .as_ref()
+            .map(|customer| customer.to_saved_queued(self.place, &self.patience))
+    }
+}
+
+fn place_translation(place: usize) -> Vec3 {
+    QUEUE_FRONT_POINT + QUEUE_PLACE_OFFSET * place as f32
+}
+
+fn place_scale(place: usize) -> Vec3 {
+    ScaleByAssetResolution::Res720p.scale()
+        * QUEUE_FRONT_SCALE
+        * (1. - 0.08 * place as f32).max(0.5)
+}
+
+/// Adds the customer at the end of the queue, at `place`. It comes in through the door and walks to its place.
+pub fn join_queue(
+    commands: &mut Commands,
+    customer: CustomerBundle,
+    place: usize,
+    patience: Timer,
+) {
+    commands
+        .spawn(SpriteBundle {
+            texture: customer.sprite_bundle.texture.clone(),
+            transform: Transform {
+                translation: QUEUE_DOOR_POINT,
+                scale: place_scale(place),
+                ..Default::default()
+            },
+            sprite: Sprite {
+                anchor: bevy::sprite::Anchor::BottomCenter,
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+        .insert(QueuedCustomer {
+            customer: Some(customer),
+            patience,
+            place,
+            leaving: false,
+        })
+        .insert(OnIngameScreen);
+}
+
+fn tick_queue_patience(
+    time: Res<Time>,
+    mut queued_customers_q: Query<(&mut QueuedCustomer, &mut Sprite)>,
+    queue_config: Res<QueueConfig>,
+    mut player_stats: ResMut<PlayerStats>,
+    mut day_stats: ResMut<DayStats>,
+    mut customer_lost_events: EventWriter<CustomerLost>,
+    current_rules: CurrentRules,
+) {
+    // Customers never give up in the game modes without patience
+    if !current_rules.rules().patience {
+        return;
+    }
+
+    for (mut queued_customer, mut sprite) in queued_customers_q.iter_mut() {
+        if queued_customer.leaving {
+            continue;
+        }
+
+        // Turn redder the closer the timer is to finishing, like the customers at the bar
+        let percent_left = queued_customer.patience.percent_left();
+        sprite.color = Color::rgb(1., percent_left, percent_left);
+
+        if queued_customer.patience.tick(time.delta()).just_finished() {
+            queued_customer.leaving = true;
+            queued_customer.customer = None;
+            sprite.color = Color::rgb(0.5, 0.5, 0.5);
+
+            player_stats.reputation_progress = player_stats
+                .reputation_progress
+                .saturating_sub(queue_config.reputation_penalty);
+            day_stats.customers_lost += 1;
+            day_stats.reputation_lost += queue_config.reputation_penalty;
+            customer_lost_events.send(CustomerLost);
+        }
+    }
+}
+
+/// Moves the first customers in line to the free slots of the bar and closes the gaps in the line
+fn seat_queued_customers(
+    mut commands: Commands,
+    mut queued_customers_q: Query<(Entity, &mut QueuedCustomer)>,
+    mut bar_q: Query<&mut Bar>,
+    mut game_rng: ResMut<GameRng>,
+) {
+    let mut bar = bar_q.single_mut();
+
+    let mut waiting = queued_customers_q
+        .iter_mut()
+        .filter(|(_, queued_customer)| !queued_customer.leaving)
+        .collect::<Vec<_>>();
+    waiting.sort_by_key(|(_, queued_customer)| queued_customer.place);
+
+    let mut place = 0;
+    for (entity, mut queued_customer) in waiting {
+        if let Some(customer) = queued_customer.customer.take() {
+            match bar.seat_customer(customer, &mut game_rng) {
+                Ok(()) => {
+                    commands.entity(entity).despawn_recursive();
+                    continue;
+                }
+                Err(customer) => queued_customer.customer = Some(*customer),
+            }
+        }
+        queued_customer.place = place;
+        place += 1;
+    }
+}
+
+/// Walks the customers to their place in line, or out of the door if they gave up
+fn move_queued_customers(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut queued_customers_q: Query<(Entity, &QueuedCustomer, &mut Transform)>,
+) {
+    for (entity, queued_customer, mut transform) in queued_customers_q.iter_mut() {
+        let target = if queued_customer.leaving {
+            QUEUE_DOOR_POINT
+        } else {
+            place_translation(queued_customer.place)
+        };
+
+        let current = transform.translation;
+        let step = QUEUE_WALK_SPEED * time.delta_seconds();
+        if current.truncate().distance(target.truncate()) > step {
+            transform.translation +=
+                (target - current).truncate().normalize_or_zero().extend(0.) * step;
+        } else {
+            transform.translation = target;
+            if queued_customer.leaving {
+                commands.entity(entity).despawn_recursive();
+                continue;
+            }
+        }
+        // The z only depends on the place, so customers further back are drawn behind
+        transform.translation.z = target.z;
+        if !queued_customer.leaving {
+            transform.scale = place_scale(queued_customer.place);
+        }
+    }
+}
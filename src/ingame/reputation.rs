@@ -0,0 +1,234 @@
+//! Reputation progression.
+//!
+//! Every served customer adds reputation progress. Once the progress reaches the maximum for the current level
+//! (given by [`ReputationConfig`]) the player levels up and a [`ReputationLevelUp`] event is sent, which makes
+//! customers less patient and more frequent and unlocks new drinks (see `DrinkDefinition::unlock_reputation`).
+
+use bevy::prelude::*;
+
+use super::{CustomersStats, IngameState, OnIngameScreen, PlayerStats};
+use crate::GameState;
+
+pub struct ReputationPlugin;
+
+impl Plugin for ReputationPlugin {
+    fn build(&self, app: &mut App) {
+        app //
+            .init_resource::<ReputationConfig>()
+            .add_event::<ReputationLevelUp>()
+            .add_systems(OnEnter(GameState::Playing), setup_reputation_hud)
+            .add_systems(
+                Update,
+                (
+                    check_reputation_level_up,
+                    apply_reputation_level_up,
+                    update_reputation_hud,
+                )
+                    .chain()
+                    .run_if(in_state(IngameState::Running)),
+            );
+    }
+}
+
+/// Reputation curve and how each level up changes the customers.
+#[derive(Resource)]
+pub struct ReputationConfig {
+    /// Progress needed to go from level 0 to level 1
+    pub base_progress: u32,
+    /// Each level needs `progress_growth` times the progress of the previous one
+    pub progress_growth: f32,
+    /// `CustomersStats.customers_wait_duration` is multiplied by this on every level up
+    pub wait_duration_factor: f32,
+    pub min_wait_duration: f32,
+    /// `CustomersStats.customers_spawn_gap` gets one second shorter every this many levels
+    pub spawn_gap_levels: u32,
+    pub min_spawn_gap: u64,
+}
+
+impl Default for ReputationConfig {
+    fn default() -> Self {
+        Self {
+            base_progress: 10,
+            progress_growth: 1.5,
+            wait_duration_factor: 0.95,
+            min_wait_duration: 1.5,
+            spawn_gap_levels: 3,
+            min_spawn_gap: 1,
+        }
+    }
+}
+
+impl ReputationConfig {
+    /// Progress needed to level up from `level`. At least 1, even for a curve starting at 0, so every level up
+    /// takes some progress
+    pub fn progress_max(&self, level: u32) -> u32 {
+        ((self.base_progress as f32 * self.progress_growth.powi(level as i32)).round() as u32)
+            .max(1)
+    }
+}
+
+/// Sent when the player reaches a new reputation level
+#[derive(Event)]
+pub struct ReputationLevelUp {
+    pub level: u32,
+}
+
+#[derive(Component)]
+struct ReputationText;
+
+#[derive(Component)]
+struct ReputationBarFill;
+
+fn setup_reputation_hud(mut commands: Commands) {
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                right: Val::Px(0.),
+                bottom: Val::Px(0.),
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::FlexEnd,
+                row_gap: Val::Px(5.),
+                padding: UiRect::all(Val::Px(10.)),
+                ..Default::default()
+            },
+            background_color: Color::rgba(1., 1., 1., 0.1).into(),
+            ..Default::default()
+        })
+        .insert(OnIngameScreen)
+        .with_children(|child_builder| {
+            child_builder
+                .spawn(TextBundle::from_section(
+                    "Reputation: 0",
+                    TextStyle {
+                        font_size: 50.,
+                        color: Color::BLACK,
+                        ..Default::default()
+                    },
+                ))
+                .insert(ReputationText);
+
+            // Bar background
+            child_builder
+                .spawn(NodeBundle {
+                    style: Style {
+                        width: Val::Px(300.),
+                        height: Val::Px(20.),
+                        ..Default::default()
+                    },
+                    background_color: Color::rgba(0., 0., 0., 0.5).into(),
+                    ..Default::default()
+                })
+                .with_children(|child_builder| {
+                    child_builder
+                        .spawn(NodeBundle {
+                            style: Style {
+                                width: Val::Percent(0.),
+                                height: Val::Percent(100.),
+                                ..Default::default()
+                            },
+                            background_color: Color::rgb(0.9, 0.7, 0.2).into(),
+                            ..Default::default()
+                        })
+                        .insert(ReputationBarFill);
+                });
+        });
+}
+
+fn check_reputation_level_up(
+    mut player_stats: ResMut<PlayerStats>,
+    reputation_config: Res<ReputationConfig>,
+    mut level_up_events: EventWriter<ReputationLevelUp>,
+) {
+    while player_stats.reputation_progress >= player_stats.reputation_progress_max {
+        player_stats.reputation_progress -= player_stats.reputation_progress_max;
+        player_stats.reputation_level += 1;
+        player_stats.reputation_progress_max =
+            reputation_config.progress_max(player_stats.reputation_level);
+        level_up_events.send(ReputationLevelUp {
+            level: player_stats.reputation_level,
+        });
+    }
+}
+
+fn apply_reputation_level_up(
+    mut level_up_events: EventReader<ReputationLevelUp>,
+    reputation_config: Res<ReputationConfig>,
+    mut customers_stats: ResMut<CustomersStats>,
+) {
+    for level_up in level_up_events.read() {
+        info!("Reputation level up: {}", level_up.level);
+
+        customers_stats.customers_wait_duration = (customers_stats.customers_wait_duration
+            * reputation_config.wait_duration_factor)
+            .max(reputation_config.min_wait_duration);
+
+        if level_up.level % reputation_config.spawn_gap_levels == 0 {
+            let spawn_gap = &mut customers_stats.customers_spawn_gap;
+            spawn_gap.end = spawn_gap
+                .end
+                .saturating_sub(1)
+                .max(spawn_gap.start + reputation_config.min_spawn_gap);
+        }
+    }
+}
+
+fn update_reputation_hud(
+    player_stats: Res<PlayerStats>,
+    mut reputation_text_q: Query<&mut Text, With<ReputationText>>,
+    mut reputation_bar_fill_q: Query<&mut Style, With<ReputationBarFill>>,
+) {
+    if !player_stats.is_changed() {
+        return;
+    }
+
+    let mut reputation_text = reputation_text_q.single_mut();
+    reputation_text.sections[0].value = format!("Reputation: {}", player_stats.reputation_level);
+
+    let mut reputation_bar_fill = reputation_bar_fill_q.single_mut();
+    reputation_bar_fill.width = Val::Percent(
+        player_stats.reputation_progress as f32 / player_stats.reputation_progress_max as f32
+            * 100.,
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn progress_max_grows_with_the_level() {
+        let reputation_config = ReputationConfig::default();
+        assert_eq!(
+            reputation_config.progress_max(0),
+            reputation_config.base_progress
+        );
+        assert_eq!(reputation_config.progress_max(1), 15);
+        assert_eq!(reputation_config.progress_max(2), 23);
+        for level in 0..20 {
+            assert!(
+                reputation_config.progress_max(level + 1) >= reputation_config.progress_max(level)
+            );
+        }
+    }
+
+    #[test]
+    fn progress_max_is_flat_without_growth() {
+        let reputation_config = ReputationConfig {
+            progress_growth: 1.,
+            ..Default::default()
+        };
+        assert_eq!(reputation_config.progress_max(0), 10);
+        assert_eq!(reputation_config.progress_max(10), 10);
+    }
+
+    #[test]
+    fn progress_max_is_never_zero() {
+        let reputation_config = ReputationConfig {
+            base_progress: 0,
+            ..Default::default()
+        };
+        assert_eq!(reputation_config.progress_max(0), 1);
+        assert_eq!(reputation_config.progress_max(5), 1);
+    }
+}
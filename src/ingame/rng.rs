@@ -0,0 +1,153 @@
+//! Seeded randomness of the gameplay.
+//!
+//! Everything random in a session (the customers and their orders, the gaps between them, the bar slot they take
+//! and where they leave their glasses) is drawn from the [`GameRng`], so the same seed plays out the same way. Every
+//! subsystem has its own [`RngStream`], which keeps one from shifting the others. A new game gets a random seed, or
+//! the one in the `TAVERN_SEED` environment variable to reproduce a run. The seed and the position of every stream
+//! are part of the save game.
+
+use bevy::prelude::*;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Serialize};
+
+/// Environment variable with the seed of the new games, for reproducing a run
+const SEED_ENV_VAR: &str = "TAVERN_SEED";
+
+/// Independent sequences of random numbers, one per subsystem
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RngStream {
+    /// Name, archetype and order of the new customers, and the gaps between them
+    Customers,
+    /// Bar slot taken by the customers
+    Seats,
+    /// Position of the dirty glasses
+    Glasses,
+}
+
+impl RngStream {
+    const ALL: [RngStream; 3] = [RngStream::Customers, RngStream::Seats, RngStream::Glasses];
+
+    fn index(self) -> usize {
+        match self {
+            RngStream::Customers => 0,
+            RngStream::Seats => 1,
+            RngStream::Glasses => 2,
+        }
+    }
+}
+
+/// Source of all the gameplay randomness. Part of the save game.
+#[derive(Resource, Clone, Serialize, Deserialize)]
+#[serde(from = "SavedGameRng", into = "SavedGameRng")]
+pub struct GameRng {
+    seed: u64,
+    streams: [ChaCha8Rng; 3],
+}
+
+impl Default for GameRng {
+    fn default() -> Self {
+        Self::from_seed(0)
+    }
+}
+
+impl GameRng {
+    pub fn from_seed(seed: u64) -> Self {
+        Self {
+            seed,
+            streams: RngStream::ALL.map(|stream| {
+                let mut rng = ChaCha8Rng::seed_from_u64(seed);
+                rng.set_stream(stream.index() as u64);
+                rng
+            }),
+        }
+    }
+
+    /// Seed of a new game: the one in `TAVERN_SEED` if set, a random one otherwise
+    pub fn new_game_seed() -> u64 {
+        std::env::var(SEED_ENV_VAR)
+            .ok()
+            .and_then(|seed| seed.trim().parse().ok())
+            .unwrap_or_else(|| rand::thread_rng().gen())
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    pub fn stream(&mut self, stream: RngStream) -> &mut ChaCha8Rng {
+        &mut self.streams[stream.index()]
+    }
+}
+
+/// How [`GameRng`] is written in the save game: the seed and how far every stream got
+#[derive(Serialize, Deserialize)]
+struct SavedGameRng {
+    seed: u64,
+    /// Word position of every stream, split in its high and low halves
+    word_positions: [(u64, u64); 3],
+}
+
+impl From<GameRng> for SavedGameRng {
+    fn from(game_rng: GameRng) -> Self {
+        Self {
+            seed: game_rng.seed,
+            word_positions: game_rng.streams.map(|rng| {
+                let word_pos = rng.get_word_pos();
+                ((word_pos >> 64) as u64, word_pos as u64)
+            }),
+        }
+    }
+}
+
+impl From<SavedGameRng> for GameRng {
+    fn from(saved: SavedGameRng) -> Self {
+        let mut game_rng = GameRng::from_seed(saved.seed);
+        for (rng, (high, low)) in game_rng.streams.iter_mut().zip(saved.word_positions) {
+            rng.set_word_pos((u128::from(high) << 64) | u128::from(low));
+        }
+        game_rng
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn same_seed_draws_the_same_numbers() {
+        let mut first = GameRng::from_seed(42);
+        let mut second = GameRng::from_seed(42);
+        for stream in RngStream::ALL {
+            let first_draws: Vec<u64> = (0..10).map(|_| first.stream(stream).gen()).collect();
+            let second_draws: Vec<u64> = (0..10).map(|_| second.stream(stream).gen()).collect();
+            assert_eq!(first_draws, second_draws);
+        }
+    }
+
+    #[test]
+    fn streams_draw_different_numbers() {
+        let mut game_rng = GameRng::from_seed(42);
+        let draws = RngStream::ALL.map(|stream| game_rng.stream(stream).gen::<u64>());
+        assert_ne!(draws[0], draws[1]);
+        assert_ne!(draws[1], draws[2]);
+    }
+
+    #[test]
+    fn saved_game_rng_resumes_every_stream() {
+        let mut game_rng = GameRng::from_seed(42);
+        for _ in 0..10 {
+            game_rng.stream(RngStream::Customers).gen::<u64>();
+        }
+        game_rng.stream(RngStream::Glasses).gen::<f32>();
+
+        let mut loaded: GameRng = ron::from_str(&ron::to_string(&game_rng).unwrap()).unwrap();
+        assert_eq!(loaded.seed(), 42);
+        for stream in RngStream::ALL {
+            assert_eq!(
+                loaded.stream(stream).gen::<u64>(),
+                game_rng.stream(stream).gen::<u64>()
+            );
+        }
+    }
+}
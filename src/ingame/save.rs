@@ -0,0 +1,495 @@
+//! Save game support.
+//!
+//! The session (game mode, random seed, player and customers stats, lives, adaptive difficulty, camera position, tray, every customer at the bar or in the
+//! queue, the current day, the purchased upgrades, the drink stock and the glasses) is written with [`crate::persistence`] when the game is paused, when going back to the main menu and
+//! periodically while running. The main menu "Continue" button restores it through [`SessionStart::Continue`], and
+//! is only enabled for saves of the current [`SAVE_VERSION`]. A save that still fails to load is copied aside before
+//! the new game started instead overwrites it. The save is deleted on game over.
+
+use std::time::Duration;
+
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    archetypes::{Archetype, Archetypes},
+    drinks::Drinks,
+    persistence::{self, Location},
+    GameMode, GameState,
+};
+
+use super::{
+    bar::{Bar, CustomerSlotMarker},
+    customer::{Customer, CustomerBundle, CustomerState},
+    difficulty::AdaptivePacing,
+    game_mode::GameModeConfig,
+    game_over::Lives,
+    glasses::{spawn_dirty_glasses, DirtyGlass, Glasses},
+    order::Order,
+    pour::{PourConfig, PouringGlass},
+    queue::{join_queue, QueuedCustomer},
+    rng::GameRng,
+    shop::Upgrades,
+    stock::Stock,
+    tray::Tray,
+    workday::{DayStats, Workday},
+    CameraPosition, CustomersStats, IngameState, PlayerStats,
+};
+
+const SAVE_KEY: &str = "savegame";
+/// Where a save game that failed to load is kept, as the new game started instead overwrites the save
+const UNLOADABLE_SAVE_KEY: &str = "savegame.unloadable";
+/// Bump when the format of [`SaveGame`] changes. Saves with a different version are ignored.
+const SAVE_VERSION: u32 = 18;
+/// Seconds between autosaves while the game is running
+const AUTOSAVE_INTERVAL: f32 = 30.;
+
+pub struct SavePlugin;
+
+impl Plugin for SavePlugin {
+    fn build(&self, app: &mut App) {
+        app //
+            .init_resource::<SessionStart>()
+            .insert_resource(AutosaveTimer(Timer::from_seconds(
+                AUTOSAVE_INTERVAL,
+                TimerMode::Repeating,
+            )))
+            .add_systems(OnEnter(GameState::Playing), start_session)
+            .add_systems(
+                Update,
+                (
+                    restore_customers.run_if(
+                        in_state(IngameState::Running)
+                            .and_then(resource_exists::<RestoredCustomers>()),
+                    ),
+                    (
+                        tick_autosave_timer,
+                        save_game.run_if(autosave_timer_finished),
+                    )
+                        .chain()
+                        .run_if(in_state(IngameState::Running)),
+                ),
+            )
+            .add_systems(OnEnter(IngameState::Paused), save_game)
+            .add_systems(OnEnter(IngameState::DaySummary), save_game)
+            .add_systems(OnEnter(IngameState::ToMenu), save_game)
+            .add_systems(OnEnter(IngameState::GameOver), delete_save);
+    }
+}
+
+/// How the next session starts when entering `GameState::Playing`. Set by the main menu.
+#[derive(Resource, Default, Clone, Copy, PartialEq, Eq)]
+pub enum SessionStart {
+    #[default]
+    NewGame,
+    Continue,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct SaveGame {
+    version: u32,
+    game_mode: GameMode,
+    game_rng: GameRng,
+    player_stats: PlayerStats,
+    lives: Lives,
+    adaptive_pacing: AdaptivePacing,
+    customers_stats: CustomersStats,
+    camera_position: CameraPosition,
+    tray: Tray,
+    customers: Vec<SavedCustomer>,
+    queue: Vec<SavedQueuedCustomer>,
+    workday: Workday,
+    day_stats: DayStats,
+    upgrades: Upgrades,
+    stock: Stock,
+    glasses: Glasses,
+    dirty_glasses: Vec<SavedDirtyGlasses>,
+}
+
+/// Only the version of a save, read before the full [`SaveGame`] to skip incompatible saves.
+#[derive(Deserialize)]
+struct SaveVersion {
+    version: u32,
+}
+
+impl SaveVersion {
+    fn is_supported(&self) -> bool {
+        self.version == SAVE_VERSION
+    }
+}
+
+impl SaveGame {
+    /// True if there is a save game this version of the game can continue
+    pub fn exists() -> bool {
+        matches!(
+            persistence::read::<SaveVersion>(Location::Data, SAVE_KEY),
+            Ok(Some(save_version)) if save_version.is_supported()
+        )
+    }
+
+    fn load() -> Option<SaveGame> {
+        match persistence::read::<SaveVersion>(Location::Data, SAVE_KEY) {
+            Ok(Some(save_version)) if !save_version.is_supported() => {
+                warn!(
+                    "Ignoring save game with version {}, expected {SAVE_VERSION}",
+                    save_version.version
+                );
+                return None;
+            }
+            Ok(None) => return None,
+            Err(error) => {
+                warn!("Failed to load save game: {error}");
+                return None;
+            }
+            _ => {}
+        }
+
+        persistence::read::<SaveGame>(Location::Data, SAVE_KEY)
+            .map_err(|error| warn!("Failed to load save game: {error}"))
+            .ok()
+            .flatten()
+    }
+
+    /// Keeps a copy of the save game that failed to load, before a new game overwrites it
+    fn set_aside() {
+        error!("Failed to continue the save game, starting a new game. The save is kept as `{UNLOADABLE_SAVE_KEY}`");
+        if let Err(error) = persistence::copy(Location::Data, SAVE_KEY, UNLOADABLE_SAVE_KEY) {
+            warn!("Failed to keep the save game: {error}");
+        }
+    }
+
+    fn delete() {
+        if let Err(error) = persistence::remove(Location::Data, SAVE_KEY) {
+            warn!("Failed to delete save game: {error}");
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct SavedCustomer {
+    pub slot: CustomerSlotMarker,
+    pub name: String,
+    pub archetype: Archetype,
+    pub order: Order,
+    pub state: SavedCustomerState,
+    pub translation: [f32; 3],
+}
+
+/// Dirty glasses left on the bar counter
+#[derive(Serialize, Deserialize)]
+pub struct SavedDirtyGlasses {
+    count: u32,
+    translation: [f32; 3],
+}
+
+/// A customer waiting in the queue at the door
+#[derive(Serialize, Deserialize)]
+pub struct SavedQueuedCustomer {
+    pub place: usize,
+    pub name: String,
+    pub archetype: Archetype,
+    pub order: Order,
+    pub patience: SavedTimer,
+}
+
+#[derive(Serialize, Deserialize)]
+pub enum SavedCustomerState {
+    Spawning,
+    Waiting(SavedTimer),
+    Drinking(SavedTimer),
+    Leaving,
+}
+
+impl From<&CustomerState> for SavedCustomerState {
+    fn from(state: &CustomerState) -> Self {
+        match state {
+            CustomerState::Spawning => SavedCustomerState::Spawning,
+            CustomerState::Waiting(timer) => SavedCustomerState::Waiting(timer.into()),
+            CustomerState::Drinking(timer) => SavedCustomerState::Drinking(timer.into()),
+            CustomerState::Leaving => SavedCustomerState::Leaving,
+        }
+    }
+}
+
+impl From<&SavedCustomerState> for CustomerState {
+    fn from(state: &SavedCustomerState) -> Self {
+        match state {
+            SavedCustomerState::Spawning => CustomerState::Spawning,
+            SavedCustomerState::Waiting(timer) => CustomerState::Waiting((*timer).into()),
+            SavedCustomerState::Drinking(timer) => CustomerState::Drinking((*timer).into()),
+            SavedCustomerState::Leaving => CustomerState::Leaving,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy)]
+pub struct SavedTimer {
+    duration: f32,
+    elapsed: f32,
+}
+
+impl From<&Timer> for SavedTimer {
+    fn from(timer: &Timer) -> Self {
+        Self {
+            duration: timer.duration().as_secs_f32(),
+            elapsed: timer.elapsed_secs(),
+        }
+    }
+}
+
+impl From<SavedTimer> for Timer {
+    fn from(saved: SavedTimer) -> Self {
+        let mut timer = Timer::from_seconds(saved.duration, TimerMode::Once);
+        timer.set_elapsed(Duration::from_secs_f32(saved.elapsed));
+        timer
+    }
+}
+
+/// Customers from the loaded save, spawned at the bar and in the queue once the bar exists.
+#[derive(Resource)]
+struct RestoredCustomers {
+    customers: Vec<SavedCustomer>,
+    queue: Vec<SavedQueuedCustomer>,
+}
+
+#[derive(Resource)]
+struct AutosaveTimer(Timer);
+
+/// What the player serves the drinks with: the tray, the drink stock and the clean glasses
+#[derive(SystemParam)]
+pub(super) struct Supplies<'w> {
+    tray: ResMut<'w, Tray>,
+    stock: ResMut<'w, Stock>,
+    glasses: ResMut<'w, Glasses>,
+}
+
+#[allow(clippy::too_many_arguments)]
+pub(super) fn start_session(
+    mut commands: Commands,
+    session_start: Res<SessionStart>,
+    mut game_mode: ResMut<GameMode>,
+    mut player_stats: ResMut<PlayerStats>,
+    mut lives: ResMut<Lives>,
+    mut adaptive_pacing: ResMut<AdaptivePacing>,
+    mut customers_stats: ResMut<CustomersStats>,
+    mut camera_position: ResMut<CameraPosition>,
+    mut game_rng: ResMut<GameRng>,
+    mut workday: ResMut<Workday>,
+    mut day_stats: ResMut<DayStats>,
+    mut upgrades: ResMut<Upgrades>,
+    mut supplies: Supplies,
+    drinks: Res<Drinks>,
+    game_mode_config: Res<GameModeConfig>,
+) {
+    let save_game = match *session_start {
+        SessionStart::NewGame => None,
+        SessionStart::Continue => {
+            let save_game = SaveGame::load();
+            if save_game.is_none() {
+                SaveGame::set_aside();
+            }
+            save_game
+        }
+    };
+
+    if let Some(save_game) = save_game {
+        *game_mode = save_game.game_mode;
+        *game_rng = save_game.game_rng;
+        *player_stats = save_game.player_stats;
+        *lives = save_game.lives;
+        *adaptive_pacing = save_game.adaptive_pacing;
+        *customers_stats = save_game.customers_stats;
+        *camera_position = save_game.camera_position;
+        *supplies.tray = save_game.tray;
+        supplies.tray.retain_valid(&drinks);
+        *workday = save_game.workday;
+        *day_stats = save_game.day_stats;
+        *upgrades = save_game.upgrades;
+        upgrades.barrels.retain(|drink| drinks.contains(*drink));
+        *supplies.stock = save_game.stock;
+        supplies.stock.retain_valid(&drinks);
+        *supplies.glasses = save_game.glasses;
+        for saved_dirty_glasses in save_game.dirty_glasses {
+            spawn_dirty_glasses(
+                &mut commands,
+                Vec3::from_array(saved_dirty_glasses.translation),
+                saved_dirty_glasses.count,
+            );
+        }
+        commands.insert_resource(RestoredCustomers {
+            customers: save_game.customers,
+            queue: save_game.queue,
+        });
+    } else {
+        // The game mode of a new game is chosen in the menu
+        let rules = game_mode_config.rules(*game_mode);
+        let seed = GameRng::new_game_seed();
+        info!("New game with seed {seed}");
+        *game_rng = GameRng::from_seed(seed);
+        *player_stats = PlayerStats::default();
+        *lives = Lives(rules.lives.unwrap_or(0));
+        *adaptive_pacing = AdaptivePacing::default();
+        *customers_stats = rules.customers_stats();
+        *camera_position = CameraPosition::default();
+        *supplies.tray = Tray::default();
+        *workday = Workday::default();
+        *day_stats = DayStats::default();
+        *upgrades = Upgrades::default();
+        *supplies.stock = Stock::default();
+        *supplies.glasses = Glasses::default();
+    }
+}
+
+fn restore_customers(
+    mut commands: Commands,
+    restored_customers: Res<RestoredCustomers>,
+    drinks: Res<Drinks>,
+    archetypes: Res<Archetypes>,
+    mut bar_q: Query<&mut Bar>,
+) {
+    let mut bar = bar_q.single_mut();
+    // Skip customers whose drinks or archetype are no longer in the catalogues
+    for saved_customer in restored_customers
+        .customers
+        .iter()
+        .filter(|saved_customer| {
+            saved_customer.order.is_valid(&drinks) && archetypes.contains(saved_customer.archetype)
+        })
+    {
+        // Skip customers sitting in a slot the bar no longer has
+        if bar.occupy_slot(&saved_customer.slot) {
+            commands
+                .spawn(CustomerBundle::from_saved(saved_customer, &archetypes))
+                .insert(saved_customer.slot);
+        }
+    }
+
+    let mut queue = restored_customers
+        .queue
+        .iter()
+        .filter(|saved_queued_customer| {
+            saved_queued_customer.order.is_valid(&drinks)
+                && archetypes.contains(saved_queued_customer.archetype)
+        })
+        .collect::<Vec<_>>();
+    queue.sort_by_key(|saved_queued_customer| saved_queued_customer.place);
+    for (place, saved_queued_customer) in queue.into_iter().enumerate() {
+        join_queue(
+            &mut commands,
+            CustomerBundle::from_saved_queued(saved_queued_customer, &archetypes),
+            place,
+            saved_queued_customer.patience.into(),
+        );
+    }
+
+    commands.remove_resource::<RestoredCustomers>();
+}
+
+fn tick_autosave_timer(time: Res<Time>, mut autosave_timer: ResMut<AutosaveTimer>) {
+    autosave_timer.0.tick(time.delta());
+}
+
+fn autosave_timer_finished(autosave_timer: Res<AutosaveTimer>) -> bool {
+    autosave_timer.0.just_finished()
+}
+
+/// The tray, with the glass being poured on it as if the pour stopped when saving
+#[derive(SystemParam)]
+struct TrayToSave<'w, 's> {
+    tray: Res<'w, Tray>,
+    pour_config: Res<'w, PourConfig>,
+    pouring_glasses_q: Query<'w, 's, &'static PouringGlass>,
+}
+
+impl TrayToSave<'_, '_> {
+    fn to_saved(&self) -> Tray {
+        let mut tray = self.tray.clone();
+        for pouring_glass in self.pouring_glasses_q.iter() {
+            tray.push(pouring_glass.poured(&self.pour_config));
+        }
+        tray
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn save_game(
+    game_mode: Res<GameMode>,
+    game_rng: Res<GameRng>,
+    player_stats: Res<PlayerStats>,
+    lives: Res<Lives>,
+    adaptive_pacing: Res<AdaptivePacing>,
+    customers_stats: Res<CustomersStats>,
+    camera_position: Res<CameraPosition>,
+    tray: TrayToSave,
+    workday: Res<Workday>,
+    day_stats: Res<DayStats>,
+    upgrades: Res<Upgrades>,
+    stock: Res<Stock>,
+    glasses: Res<Glasses>,
+    dirty_glasses_q: Query<(&DirtyGlass, &Transform)>,
+    customers_q: Query<(&Customer, &CustomerSlotMarker, &Transform)>,
+    queued_customers_q: Query<&QueuedCustomer>,
+) {
+    let save_game = SaveGame {
+        version: SAVE_VERSION,
+        game_mode: *game_mode,
+        game_rng: game_rng.clone(),
+        player_stats: player_stats.clone(),
+        lives: lives.clone(),
+        adaptive_pacing: adaptive_pacing.clone(),
+        customers_stats: customers_stats.clone(),
+        camera_position: *camera_position,
+        tray: tray.to_saved(),
+        customers: customers_q
+            .iter()
+            .map(|(customer, slot, transform)| customer.to_saved(*slot, transform))
+            .collect(),
+        queue: queued_customers_q
+            .iter()
+            .filter_map(QueuedCustomer::to_saved)
+            .collect(),
+        workday: workday.clone(),
+        day_stats: day_stats.clone(),
+        upgrades: upgrades.clone(),
+        stock: stock.clone(),
+        glasses: glasses.clone(),
+        dirty_glasses: dirty_glasses_q
+            .iter()
+            .map(|(dirty_glass, transform)| SavedDirtyGlasses {
+                count: dirty_glass.0,
+                translation: transform.translation.to_array(),
+            })
+            .collect(),
+    };
+
+    if let Err(error) = persistence::write(Location::Data, SAVE_KEY, &save_game) {
+        warn!("Failed to save the game: {error}");
+    }
+}
+
+/// A lost game can't be continued
+fn delete_save() {
+    SaveGame::delete();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn save_version_is_read_alone() {
+        let save_version: SaveVersion =
+            ron::from_str(&format!("(version: {SAVE_VERSION}, customers: [])")).unwrap();
+        assert!(save_version.is_supported());
+    }
+
+    #[test]
+    fn other_save_versions_are_ignored() {
+        for version in [0, SAVE_VERSION + 1] {
+            let save_version: SaveVersion =
+                ron::from_str(&format!("(version: {version})")).unwrap();
+            assert!(!save_version.is_supported());
+        }
+    }
+}
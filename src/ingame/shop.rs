@@ -0,0 +1,596 @@
+//! Upgrade shop.
+//!
+//! Reachable from the pause menu and from the day summary, it spends `PlayerStats.money` on [`Upgrade`]s.
+//! Purchases are stored in the [`Upgrades`] resource (part of the save game) and their effects are applied
+//! through the `Modifiers` resource. The barrels are also restocked from here (see `Stock`), and clean glasses
+//! bought (see `Glasses`).
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    despawn_screen,
+    drinks::{Drink, Drinks},
+    menu::{menu_button, ButtonColors},
+    TEXT_COLOR,
+};
+
+use super::{
+    glasses::{Glasses, GLASS_PACK_COST, GLASS_PACK_SIZE},
+    modifiers::Modifiers,
+    stock::{Stock, StockConfig},
+    workday::{Workday, WorkdayPhase},
+    IngameState, MainCameraIngame, PlayerStats, CAMERA_RESOLUTION,
+};
+
+pub struct ShopPlugin;
+
+impl Plugin for ShopPlugin {
+    fn build(&self, app: &mut App) {
+        app //
+            .init_resource::<Upgrades>()
+            .insert_resource(ShopOrigin(IngameState::Paused))
+            .add_systems(OnEnter(IngameState::Shop), setup_shop)
+            .add_systems(
+                Update,
+                (
+                    handle_shop_button,
+                    update_shop_texts,
+                    update_glasses_shop_text,
+                )
+                    .chain()
+                    .run_if(in_state(IngameState::Shop)),
+            )
+            .add_systems(OnExit(IngameState::Shop), despawn_screen::<OnShopScreen>);
+    }
+}
+
+/// State to go back to when leaving the shop
+#[derive(Resource)]
+pub struct ShopOrigin(pub IngameState);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Upgrade {
+    /// Customers wait longer
+    Patience,
+    /// Drinks are sold for more
+    DrinkPrices,
+    /// Unlocks the next locked barrel, without waiting for the reputation
+    Barrel,
+    /// Carry one more drink in the tray
+    Tray,
+    /// One more customer slot at the bar
+    Seat,
+    /// Glasses fill faster in the pouring minigame
+    PourSpeed,
+}
+
+impl Upgrade {
+    const ALL: [Upgrade; 6] = [
+        Upgrade::Patience,
+        Upgrade::DrinkPrices,
+        Upgrade::Barrel,
+        Upgrade::Tray,
+        Upgrade::Seat,
+        Upgrade::PourSpeed,
+    ];
+
+    fn name(&self) -> &'static str {
+        match self {
+            Upgrade::Patience => "Patience",
+            Upgrade::DrinkPrices => "Drink Prices",
+            Upgrade::Barrel => "New Barrel",
+            Upgrade::Tray => "Bigger Tray",
+            Upgrade::Seat => "Extra Seat",
+            Upgrade::PourSpeed => "Faster Pouring",
+        }
+    }
+
+    pub(super) fn max_level(&self) -> u32 {
+        match self {
+            Upgrade::Patience => 5,
+            Upgrade::DrinkPrices => 5,
+            Upgrade::Tray => 3,
+            Upgrade::Seat => 1,
+            Upgrade::PourSpeed => 3,
+            // Limited by the locked drinks instead
+            Upgrade::Barrel => u32::MAX,
+        }
+    }
+
+    fn cost(&self, level: u32) -> f64 {
+        let base_cost = match self {
+            Upgrade::Patience => 20.,
+            Upgrade::DrinkPrices => 30.,
+            Upgrade::Barrel => 60.,
+            Upgrade::Tray => 40.,
+            Upgrade::Seat => 80.,
+            Upgrade::PourSpeed => 25.,
+        };
+        base_cost * (level + 1) as f64
+    }
+}
+
+/// Purchased upgrades
+#[derive(Resource, Default, Clone, Serialize, Deserialize)]
+pub struct Upgrades {
+    pub patience: u32,
+    pub drink_prices: u32,
+    pub tray: u32,
+    pub seats: u32,
+    pub pour_speed: u32,
+    /// Drinks unlocked by buying their barrel
+    pub barrels: Vec<Drink>,
+}
+
+impl Upgrades {
+    pub fn level(&self, upgrade: Upgrade) -> u32 {
+        match upgrade {
+            Upgrade::Patience => self.patience,
+            Upgrade::DrinkPrices => self.drink_prices,
+            Upgrade::Barrel => self.barrels.len() as u32,
+            Upgrade::Tray => self.tray,
+            Upgrade::Seat => self.seats,
+            Upgrade::PourSpeed => self.pour_speed,
+        }
+    }
+
+    /// The drink a `Upgrade::Barrel` purchase would unlock
+    fn next_barrel(
+        &self,
+        drinks: &Drinks,
+        modifiers: &Modifiers,
+        reputation_level: u32,
+    ) -> Option<Drink> {
+        drinks
+            .iter()
+            .filter(|(drink, _)| !modifiers.is_drink_unlocked(drinks, *drink, reputation_level))
+            .min_by_key(|(_, drink_definition)| drink_definition.unlock_reputation)
+            .map(|(drink, _)| drink)
+    }
+
+    /// Returns true if `upgrade` can be bought right now
+    fn available(
+        &self,
+        upgrade: Upgrade,
+        drinks: &Drinks,
+        modifiers: &Modifiers,
+        reputation_level: u32,
+    ) -> bool {
+        self.level(upgrade) < upgrade.max_level()
+            && (upgrade != Upgrade::Barrel
+                || self
+                    .next_barrel(drinks, modifiers, reputation_level)
+                    .is_some())
+    }
+}
+
+#[derive(Component)]
+struct OnShopScreen;
+
+#[derive(Component)]
+enum ShopButtonAction {
+    Buy(Upgrade),
+    Restock(Drink),
+    BuyGlasses,
+    Back,
+}
+
+#[derive(Component)]
+struct ShopItemText(Upgrade);
+
+#[derive(Component)]
+struct ShopRestockText(Drink);
+
+#[derive(Component)]
+struct ShopGlassesText;
+
+#[derive(Component)]
+struct ShopMoneyText;
+
+fn setup_shop(
+    mut commands: Commands,
+    camera_q: Query<&Transform, With<MainCameraIngame>>,
+    drinks: Res<Drinks>,
+) {
+    // Background
+    let camera_transform = camera_q.single();
+    commands
+        .spawn(SpriteBundle {
+            transform: Transform {
+                translation: camera_transform.translation.xy().extend(111.),
+                scale: CAMERA_RESOLUTION.extend(0.),
+                ..Default::default()
+            },
+            sprite: Sprite {
+                color: Color::rgba(0., 0., 0., 0.98),
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+        .insert(OnShopScreen);
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(25.0),
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::FlexEnd,
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            OnShopScreen,
+        ))
+        .with_children(|child_builder| {
+            child_builder.spawn(TextBundle::from_section(
+                "Shop",
+                TextStyle {
+                    font_size: 120.,
+                    color: TEXT_COLOR,
+                    ..Default::default()
+                },
+            ));
+        });
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(65.0),
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    align_self: AlignSelf::End,
+                    margin: UiRect::bottom(Val::Vh(10.)),
+                    padding: UiRect::vertical(Val::Vh(10.)),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            OnShopScreen,
+        ))
+        .with_children(|child_builder| {
+            let button_style = Style {
+                width: Val::Px(600.0),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                margin: UiRect::bottom(Val::Px(25.)),
+                padding: UiRect::axes(Val::Px(15.), Val::Px(10.)),
+                ..Default::default()
+            };
+            let button_text_style = TextStyle {
+                font_size: 50.0,
+                color: TEXT_COLOR,
+                ..Default::default()
+            };
+
+            child_builder
+                .spawn(
+                    TextBundle::from_section("Money: 0", button_text_style.clone()).with_style(
+                        Style {
+                            margin: UiRect::bottom(Val::Px(35.)),
+                            ..Default::default()
+                        },
+                    ),
+                )
+                .insert(ShopMoneyText);
+
+            // The upgrades are laid out in two columns
+            child_builder
+                .spawn(NodeBundle {
+                    style: Style {
+                        width: Val::Px(1250.0),
+                        flex_direction: FlexDirection::Row,
+                        flex_wrap: FlexWrap::Wrap,
+                        justify_content: JustifyContent::SpaceBetween,
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                })
+                .with_children(|child_builder| {
+                    for upgrade in Upgrade::ALL {
+                        child_builder
+                            .spawn((
+                                ButtonBundle {
+                                    style: button_style.clone(),
+                                    background_color: ButtonColors::default().normal.into(),
+                                    ..Default::default()
+                                },
+                                ButtonColors {
+                                    hovered: Color::rgb(0.3, 0.4, 0.4),
+                                    ..Default::default()
+                                },
+                                ShopButtonAction::Buy(upgrade),
+                            ))
+                            .with_children(|child_builder| {
+                                child_builder
+                                    .spawn(
+                                        TextBundle::from_section(
+                                            upgrade.name(),
+                                            button_text_style.clone(),
+                                        )
+                                        .with_text_alignment(TextAlignment::Center)
+                                        .with_no_wrap(),
+                                    )
+                                    .insert(ShopItemText(upgrade));
+                            });
+                    }
+                });
+
+            let restock_button_style = Style {
+                margin: UiRect::bottom(Val::Px(15.)),
+                ..button_style.clone()
+            };
+            let restock_text_style = TextStyle {
+                font_size: 40.0,
+                ..button_text_style.clone()
+            };
+            for (drink, drink_definition) in drinks.iter() {
+                child_builder
+                    .spawn((
+                        ButtonBundle {
+                            style: restock_button_style.clone(),
+                            background_color: ButtonColors::default().normal.into(),
+                            ..Default::default()
+                        },
+                        ButtonColors {
+                            hovered: Color::rgb(0.3, 0.4, 0.4),
+                            ..Default::default()
+                        },
+                        ShopButtonAction::Restock(drink),
+                    ))
+                    .with_children(|child_builder| {
+                        child_builder
+                            .spawn(
+                                TextBundle::from_section(
+                                    drink_definition.name.clone(),
+                                    restock_text_style.clone(),
+                                )
+                                .with_text_alignment(TextAlignment::Center)
+                                .with_no_wrap(),
+                            )
+                            .insert(ShopRestockText(drink));
+                    });
+            }
+
+            child_builder
+                .spawn((
+                    ButtonBundle {
+                        style: restock_button_style.clone(),
+                        background_color: ButtonColors::default().normal.into(),
+                        ..Default::default()
+                    },
+                    ButtonColors {
+                        hovered: Color::rgb(0.3, 0.4, 0.4),
+                        ..Default::default()
+                    },
+                    ShopButtonAction::BuyGlasses,
+                ))
+                .with_children(|child_builder| {
+                    child_builder
+                        .spawn(
+                            TextBundle::from_section("Glasses", restock_text_style.clone())
+                                .with_text_alignment(TextAlignment::Center)
+                                .with_no_wrap(),
+                        )
+                        .insert(ShopGlassesText);
+                });
+
+            menu_button(
+                child_builder,
+                "Back",
+                ShopButtonAction::Back,
+                &Style {
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    margin: UiRect::top(Val::Px(50.)),
+                    padding: UiRect::axes(Val::Px(15.), Val::Px(10.)),
+                    ..Default::default()
+                },
+                &ButtonColors::default(),
+                &button_text_style,
+            );
+        });
+}
+
+#[allow(clippy::too_many_arguments)]
+fn handle_shop_button(
+    mut ingame_state: ResMut<NextState<IngameState>>,
+    shop_origin: Res<ShopOrigin>,
+    mut interaction_query: Query<
+        (
+            &Interaction,
+            &mut BackgroundColor,
+            &ButtonColors,
+            Option<&ShopButtonAction>,
+        ),
+        (Changed<Interaction>, With<Button>),
+    >,
+    mut player_stats: ResMut<PlayerStats>,
+    mut upgrades: ResMut<Upgrades>,
+    modifiers: Res<Modifiers>,
+    drinks: Res<Drinks>,
+    mut stock: ResMut<Stock>,
+    stock_config: Res<StockConfig>,
+    mut glasses: ResMut<Glasses>,
+    workday: Res<Workday>,
+) {
+    for (interaction, mut color, button_colors, shop_button_action) in &mut interaction_query {
+        match *interaction {
+            Interaction::Pressed => match shop_button_action {
+                Some(ShopButtonAction::Buy(upgrade)) => {
+                    let cost = upgrade.cost(upgrades.level(*upgrade));
+                    if player_stats.money < cost
+                        || !upgrades.available(
+                            *upgrade,
+                            &drinks,
+                            &modifiers,
+                            player_stats.reputation_level,
+                        )
+                    {
+                        continue;
+                    }
+
+                    match upgrade {
+                        Upgrade::Patience => upgrades.patience += 1,
+                        Upgrade::DrinkPrices => upgrades.drink_prices += 1,
+                        Upgrade::Tray => upgrades.tray += 1,
+                        Upgrade::Seat => upgrades.seats += 1,
+                        Upgrade::PourSpeed => upgrades.pour_speed += 1,
+                        Upgrade::Barrel => {
+                            if let Some(drink) = upgrades.next_barrel(
+                                &drinks,
+                                &modifiers,
+                                player_stats.reputation_level,
+                            ) {
+                                upgrades.barrels.push(drink);
+                            }
+                        }
+                    }
+                    player_stats.money -= cost;
+                }
+                Some(ShopButtonAction::Restock(drink)) => {
+                    // Only what the money pays for when a full barrel is too expensive
+                    let servings = stock.affordable_servings(
+                        *drink,
+                        player_stats.money,
+                        &drinks,
+                        &stock_config,
+                    );
+                    if servings == 0
+                        || !can_restock(
+                            *drink,
+                            &stock,
+                            &stock_config,
+                            &drinks,
+                            &modifiers,
+                            player_stats.reputation_level,
+                        )
+                    {
+                        continue;
+                    }
+
+                    // Between days the servings are added right away, during the day they are delivered
+                    if workday.phase == WorkdayPhase::Closed {
+                        stock.add(*drink, servings, &stock_config);
+                    } else {
+                        stock.order_delivery(*drink, servings, &stock_config);
+                    }
+                    player_stats.money -=
+                        servings as f64 * Stock::serving_cost(*drink, &drinks, &stock_config);
+                }
+                Some(ShopButtonAction::BuyGlasses) => {
+                    if player_stats.money < GLASS_PACK_COST {
+                        continue;
+                    }
+                    glasses.clean += GLASS_PACK_SIZE;
+                    player_stats.money -= GLASS_PACK_COST;
+                }
+                Some(ShopButtonAction::Back) => ingame_state.set(shop_origin.0),
+                None => {}
+            },
+            Interaction::Hovered => {
+                *color = button_colors.hovered.into();
+            }
+            Interaction::None => {
+                *color = button_colors.normal.into();
+            }
+        }
+    }
+}
+
+/// Returns true if the barrel can be restocked right now
+fn can_restock(
+    drink: Drink,
+    stock: &Stock,
+    stock_config: &StockConfig,
+    drinks: &Drinks,
+    modifiers: &Modifiers,
+    reputation_level: u32,
+) -> bool {
+    modifiers.is_drink_unlocked(drinks, drink, reputation_level)
+        && !stock.is_full(drink, stock_config)
+        && !stock.is_delivery_pending(drink)
+}
+
+#[allow(clippy::too_many_arguments)]
+fn update_shop_texts(
+    player_stats: Res<PlayerStats>,
+    upgrades: Res<Upgrades>,
+    modifiers: Res<Modifiers>,
+    drinks: Res<Drinks>,
+    stock: Res<Stock>,
+    stock_config: Res<StockConfig>,
+    mut shop_item_texts_q: Query<
+        (&mut Text, &ShopItemText),
+        (Without<ShopMoneyText>, Without<ShopRestockText>),
+    >,
+    mut shop_restock_texts_q: Query<
+        (&mut Text, &ShopRestockText),
+        (Without<ShopMoneyText>, Without<ShopItemText>),
+    >,
+    mut shop_money_text_q: Query<&mut Text, With<ShopMoneyText>>,
+) {
+    shop_money_text_q.single_mut().sections[0].value = format!("Money: {:.2}", player_stats.money);
+
+    for (mut text, shop_item_text) in shop_item_texts_q.iter_mut() {
+        let upgrade = shop_item_text.0;
+        let level = upgrades.level(upgrade);
+        text.sections[0].value =
+            if !upgrades.available(upgrade, &drinks, &modifiers, player_stats.reputation_level) {
+                format!("{} (Sold out)", upgrade.name())
+            } else {
+                format!(
+                    "{} Lv {} - ${}",
+                    upgrade.name(),
+                    level + 1,
+                    upgrade.cost(level)
+                )
+            };
+    }
+
+    for (mut text, shop_restock_text) in shop_restock_texts_q.iter_mut() {
+        let drink = shop_restock_text.0;
+        let name = &drinks.get(drink).name;
+        text.sections[0].value =
+            if !modifiers.is_drink_unlocked(&drinks, drink, player_stats.reputation_level) {
+                format!("{name} (Locked)")
+            } else if stock.is_delivery_pending(drink) {
+                format!("{name} delivery on its way")
+            } else if stock.is_full(drink, &stock_config) {
+                format!("{name} barrel full")
+            } else {
+                let level = stock.level(drink, &stock_config);
+                let servings =
+                    stock.affordable_servings(drink, player_stats.money, &drinks, &stock_config);
+                if servings == 0 || servings == stock.missing(drink, &stock_config) {
+                    format!(
+                        "Restock {name} {level}/{} - ${:.2}",
+                        stock_config.barrel_capacity,
+                        stock.restock_cost(drink, &drinks, &stock_config)
+                    )
+                } else {
+                    format!(
+                        "Restock {name} {level}+{servings}/{} - ${:.2}",
+                        stock_config.barrel_capacity,
+                        servings as f64 * Stock::serving_cost(drink, &drinks, &stock_config)
+                    )
+                }
+            };
+    }
+}
+
+fn update_glasses_shop_text(
+    glasses: Res<Glasses>,
+    mut shop_glasses_text_q: Query<&mut Text, With<ShopGlassesText>>,
+) {
+    shop_glasses_text_q.single_mut().sections[0].value = format!(
+        "Buy {GLASS_PACK_SIZE} glasses ({} clean) - ${GLASS_PACK_COST:.2}",
+        glasses.clean
+    );
+}
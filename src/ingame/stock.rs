@@ -0,0 +1,368 @@
+//! Drink stock of the barrels.
+//!
+//! Every barrel holds a limited number of servings, shown by a gauge next to it, and runs empty as drinks are
+//! poured. Barrels are restocked from the shop for `PlayerStats.money`, with as many servings as the money pays
+//! for: during the day they come with a delivery arriving after [`StockConfig::delivery_delay`], between days they
+//! are added right away. When every barrel is dry and the money doesn't pay for a single serving, an emergency
+//! delivery of [`StockConfig::emergency_servings`] is sent for free so that the game can go on.
+
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::drinks::{Drink, Drinks};
+
+use super::{modifiers::Modifiers, IngameState, OnIngameScreen, PlayerStats};
+
+pub struct StockPlugin;
+
+impl Plugin for StockPlugin {
+    fn build(&self, app: &mut App) {
+        app //
+            .init_resource::<StockConfig>()
+            .init_resource::<Stock>()
+            .add_systems(
+                Update,
+                (tick_deliveries, emergency_delivery).run_if(in_state(IngameState::Running)),
+            )
+            .add_systems(
+                Update,
+                update_barrel_gauges.run_if(resource_changed::<Stock>()),
+            );
+    }
+}
+
+// Constants
+const GAUGE_SIZE: Vec2 = Vec2::new(24., 150.);
+const GAUGE_BORDER: f32 = 4.;
+/// Position of the gauge relative to its barrel
+const GAUGE_OFFSET: Vec3 = Vec3::new(105., -20., 1.);
+const GAUGE_FULL_COLOR: Color = Color::rgb(0.3, 0.8, 0.3);
+const GAUGE_EMPTY_COLOR: Color = Color::rgb(0.9, 0.2, 0.2);
+
+/// Size of the barrels and price of restocking them.
+#[derive(Resource)]
+pub struct StockConfig {
+    /// Servings of a full barrel
+    pub barrel_capacity: u32,
+    /// Seconds between ordering a delivery and the barrel being refilled
+    pub delivery_delay: f32,
+    /// Fraction of the drink price paid for every serving restocked
+    pub serving_cost_factor: f64,
+    /// Servings of the free delivery sent when the player can neither pour nor restock anything
+    pub emergency_servings: u32,
+}
+
+impl Default for StockConfig {
+    fn default() -> Self {
+        Self {
+            barrel_capacity: 20,
+            delivery_delay: 20.,
+            serving_cost_factor: 0.4,
+            emergency_servings: 5,
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+struct Delivery {
+    drink: Drink,
+    servings: u32,
+    /// Seconds until it arrives
+    remaining: f32,
+}
+
+/// Servings left in every barrel and the deliveries on their way. Part of the save game.
+#[derive(Resource, Default, Clone, Serialize, Deserialize)]
+pub struct Stock {
+    /// Barrels missing from the map are full
+    levels: HashMap<Drink, u32>,
+    deliveries: Vec<Delivery>,
+}
+
+impl Stock {
+    pub fn level(&self, drink: Drink, stock_config: &StockConfig) -> u32 {
+        self.levels
+            .get(&drink)
+            .copied()
+            .unwrap_or(stock_config.barrel_capacity)
+            .min(stock_config.barrel_capacity)
+    }
+
+    /// Takes a serving from the barrel. Returns false if it is empty.
+    pub fn take(&mut self, drink: Drink, stock_config: &StockConfig) -> bool {
+        let level = self.level(drink, stock_config);
+        if level == 0 {
+            return false;
+        }
+        self.levels.insert(drink, level - 1);
+        true
+    }
+
+    pub fn is_full(&self, drink: Drink, stock_config: &StockConfig) -> bool {
+        self.level(drink, stock_config) >= stock_config.barrel_capacity
+    }
+
+    pub fn is_delivery_pending(&self, drink: Drink) -> bool {
+        self.deliveries
+            .iter()
+            .any(|delivery| delivery.drink == drink)
+    }
+
+    /// Servings missing to fill up the barrel
+    pub fn missing(&self, drink: Drink, stock_config: &StockConfig) -> u32 {
+        stock_config.barrel_capacity - self.level(drink, stock_config)
+    }
+
+    pub fn serving_cost(drink: Drink, drinks: &Drinks, stock_config: &StockConfig) -> f64 {
+        drinks.get(drink).price * stock_config.serving_cost_factor
+    }
+
+    /// Price of filling up the barrel
+    pub fn restock_cost(&self, drink: Drink, drinks: &Drinks, stock_config: &StockConfig) -> f64 {
+        self.missing(drink, stock_config) as f64 * Stock::serving_cost(drink, drinks, stock_config)
+    }
+
+    /// Servings of the barrel `money` pays for, up to filling it
+    pub fn affordable_servings(
+        &self,
+        drink: Drink,
+        money: f64,
+        drinks: &Drinks,
+        stock_config: &StockConfig,
+    ) -> u32 {
+        let serving_cost = Stock::serving_cost(drink, drinks, stock_config);
+        let affordable = if serving_cost > 0. {
+            (money / serving_cost).floor().max(0.) as u32
+        } else {
+            u32::MAX
+        };
+        affordable.min(self.missing(drink, stock_config))
+    }
+
+    /// Adds servings to the barrel, up to its capacity
+    pub fn add(&mut self, drink: Drink, servings: u32, stock_config: &StockConfig) {
+        let level = self.level(drink, stock_config) + servings;
+        if level >= stock_config.barrel_capacity {
+            self.levels.remove(&drink);
+        } else {
+            self.levels.insert(drink, level);
+        }
+    }
+
+    pub fn order_delivery(&mut self, drink: Drink, servings: u32, stock_config: &StockConfig) {
+        if !self.is_delivery_pending(drink) {
+            self.deliveries.push(Delivery {
+                drink,
+                servings,
+                remaining: stock_config.delivery_delay,
+            });
+        }
+    }
+
+    /// Drops the barrels and deliveries of drinks no longer in the catalogue (used when restoring a save)
+    pub fn retain_valid(&mut self, drinks: &Drinks) {
+        self.levels.retain(|drink, _| drinks.contains(*drink));
+        self.deliveries
+            .retain(|delivery| drinks.contains(delivery.drink));
+    }
+}
+
+/// Fill of the gauge of a barrel
+#[derive(Component)]
+struct BarrelGauge(Drink);
+
+/// Spawns the stock gauge of the barrel at `barrel_position`
+pub fn spawn_barrel_gauge(commands: &mut Commands, drink: Drink, barrel_position: Vec3) {
+    commands
+        .spawn(SpriteBundle {
+            sprite: Sprite {
+                color: Color::BLACK,
+                custom_size: Some(GAUGE_SIZE + Vec2::splat(GAUGE_BORDER * 2.)),
+                ..Default::default()
+            },
+            transform: Transform::from_translation(barrel_position + GAUGE_OFFSET),
+            ..Default::default()
+        })
+        .insert(OnIngameScreen)
+        .with_children(|child_builder| {
+            child_builder
+                .spawn(SpriteBundle {
+                    sprite: Sprite {
+                        color: GAUGE_FULL_COLOR,
+                        custom_size: Some(GAUGE_SIZE),
+                        anchor: bevy::sprite::Anchor::BottomCenter,
+                        ..Default::default()
+                    },
+                    transform: Transform::from_xyz(0., -GAUGE_SIZE.y / 2., 0.1),
+                    ..Default::default()
+                })
+                .insert(BarrelGauge(drink));
+        });
+}
+
+fn tick_deliveries(time: Res<Time>, mut stock: ResMut<Stock>, stock_config: Res<StockConfig>) {
+    if stock.deliveries.is_empty() {
+        return;
+    }
+
+    let delta = time.delta_seconds();
+    let mut arrived = Vec::new();
+    stock.deliveries.retain_mut(|delivery| {
+        delivery.remaining -= delta;
+        if delivery.remaining <= 0. {
+            arrived.push((delivery.drink, delivery.servings));
+            false
+        } else {
+            true
+        }
+    });
+    for (drink, servings) in arrived {
+        stock.add(drink, servings, &stock_config);
+    }
+}
+
+/// Sends the cheapest unlocked drink for free when nothing can be poured nor restocked
+fn emergency_delivery(
+    mut stock: ResMut<Stock>,
+    stock_config: Res<StockConfig>,
+    player_stats: Res<PlayerStats>,
+    drinks: Res<Drinks>,
+    modifiers: Res<Modifiers>,
+) {
+    if !stock.deliveries.is_empty() {
+        return;
+    }
+    let unlocked_drinks = modifiers
+        .unlocked_drinks(&drinks, player_stats.reputation_level)
+        .collect::<Vec<_>>();
+    let stuck = unlocked_drinks.iter().all(|drink| {
+        stock.level(*drink, &stock_config) == 0
+            && stock.affordable_servings(*drink, player_stats.money, &drinks, &stock_config) == 0
+    });
+    if !stuck {
+        return;
+    }
+    if let Some(drink) = unlocked_drinks
+        .into_iter()
+        .min_by(|a, b| drinks.get(*a).price.total_cmp(&drinks.get(*b).price))
+    {
+        stock.order_delivery(drink, stock_config.emergency_servings, &stock_config);
+    }
+}
+
+fn update_barrel_gauges(
+    stock: Res<Stock>,
+    stock_config: Res<StockConfig>,
+    mut gauges_q: Query<(&mut Sprite, &BarrelGauge)>,
+) {
+    for (mut sprite, barrel_gauge) in gauges_q.iter_mut() {
+        let fill = stock.level(barrel_gauge.0, &stock_config) as f32
+            / stock_config.barrel_capacity.max(1) as f32;
+        sprite.custom_size = Some(Vec2::new(GAUGE_SIZE.x, GAUGE_SIZE.y * fill));
+        sprite.color = if stock.is_delivery_pending(barrel_gauge.0) {
+            // Waiting for the delivery
+            Color::rgb(0.9, 0.8, 0.2)
+        } else {
+            let [r, g, b, _] = GAUGE_EMPTY_COLOR.as_rgba_f32();
+            let [full_r, full_g, full_b, _] = GAUGE_FULL_COLOR.as_rgba_f32();
+            Color::rgb(
+                r + (full_r - r) * fill,
+                g + (full_g - g) * fill,
+                b + (full_b - b) * fill,
+            )
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn beer() -> (Drinks, Drink) {
+        let drinks = Drinks::from_prices(&[("Beer", 2.)]);
+        let beer = drinks.iter().map(|(drink, _)| drink).next().unwrap();
+        (drinks, beer)
+    }
+
+    #[test]
+    fn barrels_start_full() {
+        let (drinks, beer) = beer();
+        let stock_config = StockConfig::default();
+        let stock = Stock::default();
+        assert_eq!(
+            stock.level(beer, &stock_config),
+            stock_config.barrel_capacity
+        );
+        assert!(stock.is_full(beer, &stock_config));
+        assert_eq!(stock.restock_cost(beer, &drinks, &stock_config), 0.);
+    }
+
+    #[test]
+    fn take_empties_the_barrel() {
+        let (_, beer) = beer();
+        let stock_config = StockConfig {
+            barrel_capacity: 2,
+            ..Default::default()
+        };
+        let mut stock = Stock::default();
+        assert!(stock.take(beer, &stock_config));
+        assert!(stock.take(beer, &stock_config));
+        assert!(!stock.take(beer, &stock_config));
+        assert_eq!(stock.level(beer, &stock_config), 0);
+
+        stock.add(beer, 5, &stock_config);
+        assert!(stock.is_full(beer, &stock_config));
+    }
+
+    #[test]
+    fn restock_cost_counts_the_missing_servings() {
+        let (drinks, beer) = beer();
+        let stock_config = StockConfig::default();
+        let mut stock = Stock::default();
+        for _ in 0..5 {
+            stock.take(beer, &stock_config);
+        }
+        let expected = 5. * 2. * stock_config.serving_cost_factor;
+        assert!((stock.restock_cost(beer, &drinks, &stock_config) - expected).abs() < 1e-9);
+    }
+
+    #[test]
+    fn one_delivery_per_barrel() {
+        let (_, beer) = beer();
+        let stock_config = StockConfig::default();
+        let mut stock = Stock::default();
+        assert!(!stock.is_delivery_pending(beer));
+        stock.order_delivery(beer, 3, &stock_config);
+        stock.order_delivery(beer, 3, &stock_config);
+        assert!(stock.is_delivery_pending(beer));
+        assert_eq!(stock.deliveries.len(), 1);
+    }
+
+    #[test]
+    fn partial_restock_with_little_money() {
+        let (drinks, beer) = beer();
+        let stock_config = StockConfig::default();
+        let mut stock = Stock::default();
+        for _ in 0..10 {
+            stock.take(beer, &stock_config);
+        }
+        // A beer serving costs 0.8
+        assert_eq!(
+            stock.affordable_servings(beer, 0.5, &drinks, &stock_config),
+            0
+        );
+        assert_eq!(
+            stock.affordable_servings(beer, 4., &drinks, &stock_config),
+            5
+        );
+        assert_eq!(
+            stock.affordable_servings(beer, 100., &drinks, &stock_config),
+            10
+        );
+
+        stock.add(beer, 5, &stock_config);
+        assert_eq!(stock.level(beer, &stock_config), 15);
+    }
+}
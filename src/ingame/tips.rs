@@ -0,0 +1,112 @@
+//! Tips paid on top of the drink prices.
+//!
+//! When an order is fulfilled the customer pays its price plus a tip that grows with how much of their wait was
+//! left (a fixed share in the game modes without patience), their archetype, the pour quality of the drinks and the
+//! streak (see [`TipConfig`]). Tips are tracked apart from the drink revenue in `PlayerStats` and `DayStats`, and
+//! shown with a floating "+$x" popup.
+
+use bevy::prelude::*;
+
+use super::{IngameState, OnIngameScreen};
+
+pub struct TipsPlugin;
+
+impl Plugin for TipsPlugin {
+    fn build(&self, app: &mut App) {
+        app //
+            .init_resource::<TipConfig>()
+            .add_systems(
+                Update,
+                float_tip_popups.run_if(in_state(IngameState::Running)),
+            );
+    }
+}
+
+// Constants
+const TIP_POPUP_DURATION: f32 = 1.2;
+const TIP_POPUP_SPEED: f32 = 120.;
+/// Position of the popup relative to the customer
+const TIP_POPUP_OFFSET: Vec3 = Vec3::new(0., 700., 30.);
+const TIP_POPUP_COLOR: Color = Color::rgb(1., 0.85, 0.2);
+
+/// How big the tips are.
+#[derive(Resource)]
+pub struct TipConfig {
+    /// Tip as a fraction of the order price, when served right away with no streak
+    pub max_rate: f64,
+    /// Every streak point adds this much to the tip multiplier
+    pub streak_bonus: f64,
+    pub max_streak_bonus: f64,
+    /// Used as the fraction of the wait left in the game modes without patience, where serving fast gives no bonus
+    pub no_patience_wait_left: f64,
+}
+
+impl Default for TipConfig {
+    fn default() -> Self {
+        Self {
+            max_rate: 0.5,
+            streak_bonus: 0.25,
+            max_streak_bonus: 2.,
+            no_patience_wait_left: 0.5,
+        }
+    }
+}
+
+impl TipConfig {
+    /// Tip for an order worth `order_price`, served with `percent_left` of the wait left.
+    /// `archetype_tip` and `pour_factor` multiply it.
+    pub fn tip(
+        &self,
+        order_price: f64,
+        percent_left: f64,
+        archetype_tip: f64,
+        pour_factor: f64,
+        streak: u32,
+    ) -> f64 {
+        let streak_factor = 1. + (self.streak_bonus * streak as f64).min(self.max_streak_bonus);
+        order_price * self.max_rate * percent_left * archetype_tip * pour_factor * streak_factor
+    }
+}
+
+#[derive(Component)]
+struct TipPopup(Timer);
+
+/// Shows "+$`tip`" floating above the customer at `customer_translation`
+pub fn spawn_tip_popup(commands: &mut Commands, customer_translation: Vec3, tip: f64) {
+    commands
+        .spawn(Text2dBundle {
+            text: Text::from_section(
+                format!("+${tip:.2}"),
+                TextStyle {
+                    font_size: 60.,
+                    color: TIP_POPUP_COLOR,
+                    ..Default::default()
+                },
+            ),
+            transform: Transform::from_translation(customer_translation + TIP_POPUP_OFFSET),
+            ..Default::default()
+        })
+        .insert(TipPopup(Timer::from_seconds(
+            TIP_POPUP_DURATION,
+            TimerMode::Once,
+        )))
+        .insert(OnIngameScreen);
+}
+
+fn float_tip_popups(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut tip_popups_q: Query<(Entity, &mut TipPopup, &mut Transform, &mut Text)>,
+) {
+    for (entity, mut tip_popup, mut transform, mut text) in tip_popups_q.iter_mut() {
+        if tip_popup.0.tick(time.delta()).finished() {
+            commands.entity(entity).despawn_recursive();
+            continue;
+        }
+        transform.translation.y += TIP_POPUP_SPEED * time.delta_seconds();
+        text.sections[0]
+            .style
+            .color
+            .set_a(tip_popup.0.percent_left());
+    }
+}
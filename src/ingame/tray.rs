@@ -0,0 +1,361 @@
+//! Tray of drinks carried to the customers.
+//!
+//! Poured drinks go to the [`Tray`], which holds up to [`TrayConfig::base_capacity`] drinks plus the bigger tray
+//! upgrades. The HUD lists its contents in the bottom left corner. Clicking a customer serves the selected drink.
+//! Another one is selected by clicking it in the list, with the number keys or cycling with Tab, and the whole tray
+//! is dumped with its button or Backspace (the drinks count as wasted and their glasses are carried to the sink).
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    actions::{Actions, GameAction},
+    drinks::Drinks,
+    menu::{menu_button, ButtonColors},
+    GameState, TEXT_COLOR,
+};
+
+use super::{
+    glasses::Glasses,
+    modifiers::Modifiers,
+    pour::{PourQuality, PouredDrink},
+    workday::DayStats,
+    IngameState, OnIngameScreen,
+};
+
+pub struct TrayPlugin;
+
+impl Plugin for TrayPlugin {
+    fn build(&self, app: &mut App) {
+        app //
+            .init_resource::<TrayConfig>()
+            .init_resource::<Tray>()
+            .add_systems(OnEnter(GameState::Playing), setup_tray_hud)
+            .add_systems(
+                Update,
+                (handle_tray_button, tray_keys).run_if(in_state(IngameState::Running)),
+            )
+            .add_systems(
+                Update,
+                update_tray_hud.run_if(
+                    in_state(GameState::Playing).and_then(
+                        resource_changed::<Tray>().or_else(resource_changed::<Modifiers>()),
+                    ),
+                ),
+            );
+    }
+}
+
+// Constants
+const SELECTED_ITEM_COLOR: Color = Color::rgb(0.25, 0.45, 0.25);
+const SELECTED_ITEM_HOVERED_COLOR: Color = Color::rgb(0.3, 0.55, 0.3);
+
+#[derive(Resource)]
+pub struct TrayConfig {
+    /// Drinks carried without upgrades
+    pub base_capacity: usize,
+}
+
+impl Default for TrayConfig {
+    fn default() -> Self {
+        Self { base_capacity: 2 }
+    }
+}
+
+/// Drinks ready to be served. Part of the save game.
+#[derive(Resource, Default, Clone, Serialize, Deserialize)]
+pub struct Tray {
+    items: Vec<PouredDrink>,
+    /// Index of the item served next
+    selected: usize,
+}
+
+impl Tray {
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    pub fn push(&mut self, poured_drink: PouredDrink) {
+        self.items.push(poured_drink);
+    }
+
+    /// Removes the selected drink, to serve it
+    pub fn take_selected(&mut self) -> Option<PouredDrink> {
+        if self.selected >= self.items.len() {
+            return None;
+        }
+        let poured_drink = self.items.remove(self.selected);
+        self.selected = self.selected.min(self.items.len().saturating_sub(1));
+        Some(poured_drink)
+    }
+
+    pub fn select(&mut self, index: usize) {
+        if index < self.items.len() {
+            self.selected = index;
+        }
+    }
+
+    fn select_next(&mut self) {
+        if !self.items.is_empty() {
+            self.selected = (self.selected + 1) % self.items.len();
+        }
+    }
+
+    /// Empties the tray, returning the number of drinks thrown away
+    pub fn dump(&mut self) -> usize {
+        self.selected = 0;
+        self.items.drain(..).count()
+    }
+
+    /// Drops the drinks no longer in the catalogue (used when restoring a save)
+    pub fn retain_valid(&mut self, drinks: &Drinks) {
+        self.items
+            .retain(|poured_drink| drinks.contains(poured_drink.drink));
+        self.selected = self.selected.min(self.items.len().saturating_sub(1));
+    }
+}
+
+#[derive(Component)]
+struct TrayHeaderText;
+
+/// Parent of the tray item buttons, rebuilt every time the tray changes
+#[derive(Component)]
+struct TrayList;
+
+#[derive(Component)]
+enum TrayButtonAction {
+    Select(usize),
+    Dump,
+}
+
+fn setup_tray_hud(mut commands: Commands) {
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    left: Val::Px(0.),
+                    bottom: Val::Px(0.),
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::FlexStart,
+                    padding: UiRect::all(Val::Px(10.)),
+                    row_gap: Val::Px(5.),
+                    ..Default::default()
+                },
+                background_color: Color::rgba(1., 1., 1., 0.1).into(),
+                ..Default::default()
+            },
+            OnIngameScreen,
+        ))
+        .with_children(|child_builder| {
+            child_builder
+                .spawn(TextBundle::from_section(
+                    "Tray",
+                    TextStyle {
+                        font_size: 50.,
+                        color: Color::BLACK,
+                        ..Default::default()
+                    },
+                ))
+                .insert(TrayHeaderText);
+            child_builder
+                .spawn(NodeBundle {
+                    style: Style {
+                        flex_direction: FlexDirection::Column,
+                        row_gap: Val::Px(5.),
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                })
+                .insert(TrayList);
+            menu_button(
+                child_builder,
+                "Dump",
+                TrayButtonAction::Dump,
+                &Style {
+                    padding: UiRect::axes(Val::Px(10.), Val::Px(5.)),
+                    ..Default::default()
+                },
+                &ButtonColors {
+                    normal: Color::rgb(0.45, 0.2, 0.2),
+                    hovered: Color::rgb(0.6, 0.25, 0.25),
+                },
+                &TextStyle {
+                    font_size: 35.,
+                    color: TEXT_COLOR,
+                    ..Default::default()
+                },
+            );
+        });
+}
+
+fn update_tray_hud(
+    mut commands: Commands,
+    tray: Res<Tray>,
+    tray_config: Res<TrayConfig>,
+    modifiers: Res<Modifiers>,
+    drinks: Res<Drinks>,
+    mut tray_header_text_q: Query<&mut Text, With<TrayHeaderText>>,
+    tray_list_q: Query<Entity, With<TrayList>>,
+) {
+    let Ok(mut tray_header_text) = tray_header_text_q.get_single_mut() else {
+        return;
+    };
+    tray_header_text.sections[0].value = format!(
+        "Tray {}/{}",
+        tray.len(),
+        modifiers.tray_capacity(tray_config.base_capacity)
+    );
+
+    let tray_list = tray_list_q.single();
+    commands.entity(tray_list).despawn_descendants();
+    commands.entity(tray_list).with_children(|child_builder| {
+        for (index, poured_drink) in tray.items.iter().enumerate() {
+            let name = &drinks.get(poured_drink.drink).name;
+            let text = match poured_drink.quality {
+                PourQuality::Instant => format!("{} {name}", index + 1),
+                quality => format!("{} {name} ({quality:?})", index + 1),
+            };
+            let button_colors = if index == tray.selected {
+                ButtonColors {
+                    normal: SELECTED_ITEM_COLOR,
+                    hovered: SELECTED_ITEM_HOVERED_COLOR,
+                }
+            } else {
+                ButtonColors::default()
+            };
+            menu_button(
+                child_builder,
+                &text,
+                TrayButtonAction::Select(index),
+                &Style {
+                    padding: UiRect::axes(Val::Px(10.), Val::Px(5.)),
+                    ..Default::default()
+                },
+                &button_colors,
+                &TextStyle {
+                    font_size: 40.,
+                    color: TEXT_COLOR,
+                    ..Default::default()
+                },
+            );
+        }
+    });
+}
+
+fn dump_tray(tray: &mut Tray, day_stats: &mut DayStats, glasses: &mut Glasses) {
+    let dumped = tray.dump() as u32;
+    day_stats.wasted_drinks += dumped;
+    glasses.dirty_carried += dumped;
+}
+
+fn handle_tray_button(
+    interaction_query: Query<
+        (&Interaction, &TrayButtonAction),
+        (Changed<Interaction>, With<Button>),
+    >,
+    mut tray: ResMut<Tray>,
+    mut day_stats: ResMut<DayStats>,
+    mut glasses: ResMut<Glasses>,
+) {
+    for (interaction, tray_button_action) in &interaction_query {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        match tray_button_action {
+            TrayButtonAction::Select(index) => tray.select(*index),
+            TrayButtonAction::Dump => dump_tray(&mut tray, &mut day_stats, &mut glasses),
+        }
+    }
+}
+
+fn tray_keys(
+    actions: Res<Actions>,
+    mut tray: ResMut<Tray>,
+    mut day_stats: ResMut<DayStats>,
+    mut glasses: ResMut<Glasses>,
+) {
+    for action in actions.iter_just_pressed() {
+        match action {
+            GameAction::SelectTrayItem(index) => tray.select(index),
+            GameAction::NextTrayItem => tray.select_next(),
+            GameAction::DumpTray if !tray.is_empty() => {
+                dump_tray(&mut tray, &mut day_stats, &mut glasses)
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tray_of(count: usize) -> (Tray, Vec<PouredDrink>) {
+        let drinks = Drinks::from_prices(&[("Beer", 2.), ("Wine", 5.), ("Whiskey", 11.)]);
+        let poured_drinks: Vec<_> = drinks
+            .iter()
+            .take(count)
+            .map(|(drink, _)| PouredDrink {
+                drink,
+                quality: PourQuality::Instant,
+            })
+            .collect();
+        let mut tray = Tray::default();
+        for poured_drink in &poured_drinks {
+            tray.push(*poured_drink);
+        }
+        (tray, poured_drinks)
+    }
+
+    #[test]
+    fn take_selected_serves_in_order() {
+        let (mut tray, poured_drinks) = tray_of(3);
+        assert_eq!(tray.len(), 3);
+        for poured_drink in poured_drinks {
+            assert_eq!(tray.take_selected(), Some(poured_drink));
+        }
+        assert!(tray.is_empty());
+        assert_eq!(tray.take_selected(), None);
+    }
+
+    #[test]
+    fn select_ignores_missing_items() {
+        let (mut tray, poured_drinks) = tray_of(3);
+        tray.select(2);
+        tray.select(5);
+        assert_eq!(tray.take_selected(), Some(poured_drinks[2]));
+        // The selection moves back to the last item
+        assert_eq!(tray.take_selected(), Some(poured_drinks[1]));
+    }
+
+    #[test]
+    fn select_next_wraps_around() {
+        let (mut tray, poured_drinks) = tray_of(2);
+        tray.select_next();
+        tray.select_next();
+        assert_eq!(tray.take_selected(), Some(poured_drinks[0]));
+    }
+
+    #[test]
+    fn dump_empties_the_tray() {
+        let (mut tray, _) = tray_of(3);
+        tray.select(1);
+        assert_eq!(tray.dump(), 3);
+        assert!(tray.is_empty());
+        assert_eq!(tray.dump(), 0);
+    }
+
+    #[test]
+    fn retain_valid_drops_removed_drinks() {
+        let (mut tray, poured_drinks) = tray_of(3);
+        tray.select(2);
+        tray.retain_valid(&Drinks::from_prices(&[("Beer", 2.)]));
+        assert_eq!(tray.len(), 1);
+        assert_eq!(tray.take_selected(), Some(poured_drinks[0]));
+    }
+}
@@ -0,0 +1,447 @@
+//! Day/shift cycle of the tavern.
+//!
+//! A day starts with the tavern open. When the day timer runs out it is closing time: no more customers come in
+//! and once the last one leaves (including the ones in the queue) the day ends with `IngameState::DaySummary`, a screen showing the [`DayStats`].
+//! From there the player continues to the next day, with customers a bit more demanding, or finishes the career
+//! after its last day. In the game modes without day cycle (see `GameModeRules::day_cycle`) the tavern never closes.
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    despawn_screen,
+    menu::{menu_button, ButtonColors},
+    GameState, TEXT_COLOR,
+};
+
+use super::{
+    bar::Bar, game_mode::CurrentRules, game_over::GameOverReason, queue::QueuedCustomer,
+    shop::ShopOrigin, CustomersStats, IngameState, MainCameraIngame, OnIngameScreen, PlayerStats,
+    CAMERA_RESOLUTION,
+};
+
+pub struct WorkdayPlugin;
+
+impl Plugin for WorkdayPlugin {
+    fn build(&self, app: &mut App) {
+        app //
+            .init_resource::<WorkdayConfig>()
+            .init_resource::<Workday>()
+            .init_resource::<DayStats>()
+            .add_systems(OnEnter(GameState::Playing), setup_workday_text)
+            .add_systems(
+                Update,
+                (tick_workday, close_when_empty, update_workday_text)
+                    .chain()
+                    .run_if(in_state(IngameState::Running)),
+            )
+            // IngameState::DaySummary
+            .add_systems(OnEnter(IngameState::DaySummary), setup_day_summary)
+            .add_systems(
+                Update,
+                handle_day_summary_button.run_if(in_state(IngameState::DaySummary)),
+            )
+            .add_systems(
+                OnExit(IngameState::DaySummary),
+                despawn_screen::<OnDaySummaryScreen>,
+            );
+    }
+}
+
+/// Length of a day and how customers change from one day to the next.
+#[derive(Resource)]
+pub struct WorkdayConfig {
+    /// Seconds the tavern is open each day
+    pub day_duration: f32,
+    /// `CustomersStats.customers_wait_duration` is multiplied by this every new day
+    pub wait_duration_factor: f32,
+    pub min_wait_duration: f32,
+    /// `CustomersStats.customers_spawn_gap` gets one second shorter every this many days
+    pub spawn_gap_days: u32,
+    pub min_spawn_gap: u64,
+}
+
+impl Default for WorkdayConfig {
+    fn default() -> Self {
+        Self {
+            day_duration: 180.,
+            wait_duration_factor: 0.97,
+            min_wait_duration: 1.5,
+            spawn_gap_days: 2,
+            min_spawn_gap: 1,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum WorkdayPhase {
+    /// Customers keep coming in
+    Open,
+    /// Closing time, waiting for the last customers to leave
+    Closing,
+    /// The day is over, the summary is shown
+    Closed,
+}
+
+#[derive(Resource, Clone, Serialize, Deserialize)]
+pub struct Workday {
+    pub day: u32,
+    /// Seconds since the tavern opened
+    pub elapsed: f32,
+    pub phase: WorkdayPhase,
+}
+
+impl Default for Workday {
+    fn default() -> Self {
+        Self {
+            day: 1,
+            elapsed: 0.,
+            phase: WorkdayPhase::Open,
+        }
+    }
+}
+
+/// Run condition for the systems that bring new customers in
+pub fn tavern_is_open(workday: Res<Workday>) -> bool {
+    workday.phase == WorkdayPhase::Open
+}
+
+/// Stats of the current day, shown in the summary
+#[derive(Resource, Default, Clone, Serialize, Deserialize)]
+pub struct DayStats {
+    pub customers_served: u32,
+    pub customers_lost: u32,
+    /// Drink prices and tips
+    pub money_earned: f64,
+    pub tips_earned: f64,
+    pub highest_streak: u32,
+    pub reputation_gained: u32,
+    pub reputation_lost: u32,
+    /// Drinks handed to customers that did not order them
+    pub wasted_drinks: u32,
+}
+
+#[derive(Component)]
+struct WorkdayText;
+
+#[derive(Component)]
+struct OnDaySummaryScreen;
+
+#[derive(Component)]
+enum DaySummaryButtonAction {
+    NextDay,
+    /// Ends the career after its last day
+    Finish,
+    Shop,
+}
+
+fn tick_workday(
+    time: Res<Time>,
+    workday_config: Res<WorkdayConfig>,
+    mut workday: ResMut<Workday>,
+    current_rules: CurrentRules,
+) {
+    if current_rules.rules().day_cycle && workday.phase == WorkdayPhase::Open {
+        workday.elapsed += time.delta_seconds();
+        if workday.elapsed >= workday_config.day_duration {
+            workday.phase = WorkdayPhase::Closing;
+        }
+    }
+}
+
+fn close_when_empty(
+    mut workday: ResMut<Workday>,
+    bar_q: Query<&Bar>,
+    queued_customers_q: Query<(), With<QueuedCustomer>>,
+    mut ingame_state: ResMut<NextState<IngameState>>,
+) {
+    match workday.phase {
+        WorkdayPhase::Open => {}
+        WorkdayPhase::Closing => {
+            if bar_q.single().is_empty() && queued_customers_q.is_empty() {
+                workday.phase = WorkdayPhase::Closed;
+                ingame_state.set(IngameState::DaySummary);
+            }
+        }
+        // Loaded a save from the end of a day
+        WorkdayPhase::Closed => ingame_state.set(IngameState::DaySummary),
+    }
+}
+
+fn update_workday_text(
+    workday: Res<Workday>,
+    workday_config: Res<WorkdayConfig>,
+    mut workday_text_q: Query<&mut Text, With<WorkdayText>>,
+    current_rules: CurrentRules,
+    player_stats: Res<PlayerStats>,
+) {
+    let mut workday_text = workday_text_q.single_mut();
+    let rules = current_rules.rules();
+    if !rules.day_cycle {
+        workday_text.sections[0].value = match rules.time_limit {
+            Some(time_limit) => {
+                let seconds_left = (time_limit - player_stats.play_time).max(0.) as u32;
+                format!("Time left {}:{:02}", seconds_left / 60, seconds_left % 60)
+            }
+            None => "Open all day".to_string(),
+        };
+        return;
+    }
+
+    workday_text.sections[0].value = match workday.phase {
+        WorkdayPhase::Open => {
+            let seconds_left = (workday_config.day_duration - workday.elapsed).max(0.) as u32;
+            format!(
+                "Day {} - {}:{:02}",
+                workday.day,
+                seconds_left / 60,
+                seconds_left % 60
+            )
+        }
+        WorkdayPhase::Closing | WorkdayPhase::Closed => {
+            format!("Day {} - Closing time", workday.day)
+        }
+    };
+}
+
+fn setup_day_summary(
+    mut commands: Commands,
+    workday: Res<Workday>,
+    day_stats: Res<DayStats>,
+    camera_q: Query<&Transform, With<MainCameraIngame>>,
+    current_rules: CurrentRules,
+) {
+    // Background
+    let camera_transform = camera_q.single();
+    commands
+        .spawn(SpriteBundle {
+            transform: Transform {
+                translation: camera_transform.translation.xy().extend(111.),
+                scale: CAMERA_RESOLUTION.extend(0.),
+                ..Default::default()
+            },
+            sprite: Sprite {
+                color: Color::rgba(0., 0., 0., 0.95),
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+        .insert(OnDaySummaryScreen);
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(25.0),
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::FlexEnd,
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            OnDaySummaryScreen,
+        ))
+        .with_children(|child_builder| {
+            child_builder.spawn(TextBundle::from_section(
+                format!("Day {} is over", workday.day),
+                TextStyle {
+                    font_size: 120.,
+                    color: TEXT_COLOR,
+                    ..Default::default()
+                },
+            ));
+        });
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(65.0),
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    align_self: AlignSelf::End,
+                    margin: UiRect::bottom(Val::Vh(10.)),
+                    padding: UiRect::vertical(Val::Vh(10.)),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            OnDaySummaryScreen,
+        ))
+        .with_children(|child_builder| {
+            let text_style = TextStyle {
+                font_size: 50.0,
+                color: TEXT_COLOR,
+                ..Default::default()
+            };
+
+            for line in [
+                format!("Customers served: {}", day_stats.customers_served),
+                format!("Customers lost: {}", day_stats.customers_lost),
+                format!("Money earned: {:.2}", day_stats.money_earned),
+                format!(
+                    "Drinks: {:.2} | Tips: {:.2}",
+                    day_stats.money_earned - day_stats.tips_earned,
+                    day_stats.tips_earned
+                ),
+                format!("Highest streak: {}", day_stats.highest_streak),
+                format!("Reputation gained: {}", day_stats.reputation_gained),
+                format!("Reputation lost: {}", day_stats.reputation_lost),
+                format!("Wasted drinks: {}", day_stats.wasted_drinks),
+            ] {
+                child_builder.spawn(
+                    TextBundle::from_section(line, text_style.clone()).with_style(Style {
+                        margin: UiRect::bottom(Val::Px(15.)),
+                        ..Default::default()
+                    }),
+                );
+            }
+
+            let button_style = Style {
+                width: Val::Px(300.0),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                margin: UiRect::top(Val::Px(25.)),
+                padding: UiRect::axes(Val::Px(15.), Val::Px(10.)),
+                ..Default::default()
+            };
+
+            let last_day = current_rules
+                .rules()
+                .days_to_win
+                .is_some_and(|days_to_win| workday.day >= days_to_win);
+            let (text, action) = if last_day {
+                ("Finish", DaySummaryButtonAction::Finish)
+            } else {
+                ("Next Day", DaySummaryButtonAction::NextDay)
+            };
+            menu_button(
+                child_builder,
+                text,
+                action,
+                &Style {
+                    margin: UiRect::top(Val::Px(50.)),
+                    ..button_style.clone()
+                },
+                &ButtonColors {
+                    hovered: Color::rgb(0.3, 0.4, 0.4),
+                    ..Default::default()
+                },
+                &text_style,
+            );
+
+            menu_button(
+                child_builder,
+                "Shop",
+                DaySummaryButtonAction::Shop,
+                &button_style,
+                &ButtonColors::default(),
+                &text_style,
+            );
+        });
+}
+
+#[allow(clippy::too_many_arguments)]
+fn handle_day_summary_button(
+    mut ingame_state: ResMut<NextState<IngameState>>,
+    mut interaction_query: Query<
+        (
+            &Interaction,
+            &mut BackgroundColor,
+            &ButtonColors,
+            Option<&DaySummaryButtonAction>,
+        ),
+        (Changed<Interaction>, With<Button>),
+    >,
+    mut workday: ResMut<Workday>,
+    mut day_stats: ResMut<DayStats>,
+    workday_config: Res<WorkdayConfig>,
+    mut customers_stats: ResMut<CustomersStats>,
+    mut shop_origin: ResMut<ShopOrigin>,
+    mut game_over_reason: ResMut<GameOverReason>,
+) {
+    for (interaction, mut color, button_colors, day_summary_button_action) in &mut interaction_query
+    {
+        match *interaction {
+            Interaction::Pressed => match day_summary_button_action {
+                Some(DaySummaryButtonAction::NextDay) => {
+                    start_next_day(
+                        &mut workday,
+                        &mut day_stats,
+                        &workday_config,
+                        &mut customers_stats,
+                    );
+                    ingame_state.set(IngameState::Running);
+                }
+                Some(DaySummaryButtonAction::Finish) => {
+                    *game_over_reason = GameOverReason::CareerComplete;
+                    ingame_state.set(IngameState::GameOver);
+                }
+                Some(DaySummaryButtonAction::Shop) => {
+                    shop_origin.0 = IngameState::DaySummary;
+                    ingame_state.set(IngameState::Shop);
+                }
+                None => {}
+            },
+            Interaction::Hovered => {
+                *color = button_colors.hovered.into();
+            }
+            Interaction::None => {
+                *color = button_colors.normal.into();
+            }
+        }
+    }
+}
+
+fn start_next_day(
+    workday: &mut Workday,
+    day_stats: &mut DayStats,
+    workday_config: &WorkdayConfig,
+    customers_stats: &mut CustomersStats,
+) {
+    *workday = Workday {
+        day: workday.day + 1,
+        ..Default::default()
+    };
+    *day_stats = DayStats::default();
+
+    customers_stats.customers_wait_duration = (customers_stats.customers_wait_duration
+        * workday_config.wait_duration_factor)
+        .max(workday_config.min_wait_duration);
+    if workday.day % workday_config.spawn_gap_days == 0 {
+        let spawn_gap = &mut customers_stats.customers_spawn_gap;
+        spawn_gap.end = spawn_gap
+            .end
+            .saturating_sub(1)
+            .max(spawn_gap.start + workday_config.min_spawn_gap);
+    }
+}
+
+fn setup_workday_text(mut commands: Commands) {
+    commands
+        .spawn(
+            TextBundle::from_section(
+                "Day 1",
+                TextStyle {
+                    font_size: 50.,
+                    color: Color::BLACK,
+                    ..Default::default()
+                },
+            )
+            .with_text_alignment(TextAlignment::Center)
+            .with_background_color(Color::rgba(1., 1., 1., 0.1))
+            .with_style(Style {
+                position_type: PositionType::Absolute,
+                left: Val::Percent(40.),
+                top: Val::Px(80.),
+                padding: UiRect::all(Val::Px(10.)),
+                ..Default::default()
+            }),
+        )
+        .insert(WorkdayText)
+        .insert(OnIngameScreen);
+}
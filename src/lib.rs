@@ -1,14 +1,19 @@
 #![allow(clippy::type_complexity)]
 
-// mod actions;
+mod actions;
+mod archetypes;
 #[allow(dead_code, unused)]
 mod audio;
+mod config;
+mod drinks;
 mod ingame;
 mod loading;
 mod menu;
+mod persistence;
 
-// use crate::actions::ActionsPlugin;
+use crate::actions::{Actions, ActionsPlugin, GameAction};
 use crate::audio::InternalAudioPlugin;
+use crate::config::ConfigPlugin;
 use crate::ingame::IngamePlugin;
 use crate::loading::LoadingPlugin;
 use crate::menu::MenuPlugin;
@@ -18,6 +23,7 @@ use bevy::app::{App, AppExit};
 #[cfg(debug_assertions)]
 use bevy::diagnostic::{FrameTimeDiagnosticsPlugin, LogDiagnosticsPlugin};
 use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
 
 // Constants
 pub const CAMERA_RESOLUTION: Vec2 = Vec2::new(1920., 1080.);
@@ -36,27 +42,80 @@ enum GameState {
     Playing,
     // Here the menu is drawn and waiting for player interaction
     Menu,
+    // Left right away for `Playing`, to start a new session without going through the menu
+    Restarting,
 }
 
 // Config
 // WindowMode
-#[derive(Resource, Debug, Component, PartialEq, Eq, Clone, Copy)]
+#[derive(Resource, Debug, Component, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
 pub enum ScreenMode {
     Windowed,
     BorderlessFullscreen,
 }
 
+// Difficulty
+#[derive(
+    Resource, Debug, Component, Default, PartialEq, Eq, Clone, Copy, Serialize, Deserialize,
+)]
+pub enum Difficulty {
+    Easy,
+    #[default]
+    Normal,
+    Hard,
+}
+
+// AdaptiveDifficulty
+/// Whether the customers adapt to how well the player is doing, on top of the `Difficulty`
+#[derive(
+    Resource, Debug, Component, Default, PartialEq, Eq, Clone, Copy, Serialize, Deserialize,
+)]
+pub enum AdaptiveDifficulty {
+    #[default]
+    Off,
+    On,
+}
+
+// PourMode
+/// Whether drinks are poured with the pouring minigame or taken from the barrel instantly
+#[derive(
+    Resource, Debug, Component, Default, PartialEq, Eq, Clone, Copy, Serialize, Deserialize,
+)]
+pub enum PourMode {
+    #[default]
+    Pour,
+    Instant,
+}
+
+// GameMode
+/// Rules of the session, chosen in the menu before starting a new game
+#[derive(
+    Resource, Debug, Component, Default, PartialEq, Eq, Clone, Copy, Serialize, Deserialize,
+)]
+pub enum GameMode {
+    /// Day after day, the tavern never closes for good
+    #[default]
+    Endless,
+    /// Every lost customer costs a life, until the last day of the career
+    Career,
+    /// Earn as much money as possible before the time is up
+    TimeAttack,
+    /// Customers never run out of patience
+    Zen,
+}
+
 pub struct GamePlugin;
 
 impl Plugin for GamePlugin {
     fn build(&self, app: &mut App) {
         app //
             .add_state::<GameState>()
-            .insert_resource(ScreenMode::Windowed)
+            .init_resource::<GameMode>()
             .add_plugins((
+                ConfigPlugin,
                 LoadingPlugin,
                 MenuPlugin,
-                // ActionsPlugin,
+                ActionsPlugin,
                 InternalAudioPlugin,
                 IngamePlugin,
             ))
@@ -83,13 +142,8 @@ fn debug_exit_with_ctrl_w(
     }
 }
 
-fn set_screen_mode_with_keys(
-    keyboard_input: Res<Input<KeyCode>>,
-    mut screen_mode: ResMut<ScreenMode>,
-) {
-    if (keyboard_input.pressed(KeyCode::AltLeft) && keyboard_input.just_pressed(KeyCode::Return))
-        || keyboard_input.just_pressed(KeyCode::F)
-    {
+fn set_screen_mode_with_keys(actions: Res<Actions>, mut screen_mode: ResMut<ScreenMode>) {
+    if actions.just_pressed(GameAction::ToggleFullscreen) {
         match *screen_mode {
             ScreenMode::Windowed => {
                 *screen_mode = ScreenMode::BorderlessFullscreen;
@@ -1,3 +1,5 @@
+use crate::archetypes::{ArchetypeCatalogue, ArchetypeCatalogueLoader, Archetypes};
+use crate::drinks::{DrinkCatalogue, DrinkCatalogueLoader, Drinks};
 use crate::GameState;
 use bevy::prelude::*;
 use bevy_asset_loader::prelude::*;
@@ -10,13 +12,22 @@ pub struct LoadingPlugin;
 /// If interested, take a look at <https://bevy-cheatbook.github.io/features/assets.html>
 impl Plugin for LoadingPlugin {
     fn build(&self, app: &mut App) {
-        app.add_loading_state(
-            LoadingState::new(GameState::Loading)
-                .continue_to_state(GameState::Menu)
-                // .on_failure_continue_to_state(GameState::Menu) // Bad
-                .load_collection::<AudioAssets>()
-                .load_collection::<TextureAssets>(),
-        );
+        app //
+            .init_asset::<DrinkCatalogue>()
+            .init_asset_loader::<DrinkCatalogueLoader>()
+            .init_asset::<ArchetypeCatalogue>()
+            .init_asset_loader::<ArchetypeCatalogueLoader>()
+            .add_loading_state(
+                LoadingState::new(GameState::Loading)
+                    .continue_to_state(GameState::Menu)
+                    // .on_failure_continue_to_state(GameState::Menu) // Bad
+                    .load_collection::<AudioAssets>()
+                    .load_collection::<TextureAssets>()
+                    .load_collection::<DrinkAssets>()
+                    .load_collection::<ArchetypeAssets>()
+                    .init_resource::<Drinks>()
+                    .init_resource::<Archetypes>(),
+            );
     }
 }
 
@@ -37,8 +48,16 @@ pub struct TextureAssets {
     pub tavern_bg: Handle<Image>,
     #[asset(path = "textures/bar.png")]
     pub bar: Handle<Image>,
-    #[asset(path = "textures/barrel.png")]
-    pub barrel: Handle<Image>,
-    #[asset(path = "textures/customers/gray_outlined.png")]
-    pub customer1: Handle<Image>,
+}
+
+#[derive(AssetCollection, Resource)]
+pub struct DrinkAssets {
+    #[asset(path = "data/catalogue.drinks.ron")]
+    pub catalogue: Handle<DrinkCatalogue>,
+}
+
+#[derive(AssetCollection, Resource)]
+pub struct ArchetypeAssets {
+    #[asset(path = "data/archetypes.customers.ron")]
+    pub catalogue: Handle<ArchetypeCatalogue>,
 }
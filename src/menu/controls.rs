@@ -0,0 +1,333 @@
+//! Controls page of the settings, where the keyboard bindings are changed.
+//!
+//! Every action of `GameAction::rebindable` has two binding slots. Clicking one waits for the next key press
+//! (Escape cancels) and binds it, with the modifier held if any. Keys bound to more than one action are shown in
+//! red, with a notice naming the actions. The page is shared by the main menu and the pause menu.
+
+use bevy::prelude::*;
+
+use crate::actions::{Actions, GameAction, KeyBindings, KeyCapture};
+use crate::drinks::Drinks;
+use crate::ingame::BarConfig;
+use crate::TEXT_COLOR;
+
+use super::{menu_button, ButtonColors, MenuButtonAction, MenuState};
+
+/// Binding slots of every action
+const SLOTS: usize = 2;
+const CONFLICT_COLOR: Color = Color::rgb(1., 0.3, 0.3);
+const WAITING_COLOR: Color = Color::rgb(1., 0.85, 0.2);
+
+#[derive(Component)]
+pub struct OnControlsScreen;
+
+/// Button of the `slot`th binding of `action`
+#[derive(Component, Clone, Copy, PartialEq, Eq)]
+pub struct BindingSlot {
+    action: GameAction,
+    slot: usize,
+}
+
+#[derive(Component)]
+pub struct ResetBindingsButton;
+
+#[derive(Component)]
+pub struct ControlsNotice;
+
+/// Binding slot waiting for a key press
+#[derive(Resource, Default)]
+pub struct Rebinding(Option<BindingSlot>);
+
+pub fn controls_main_menu_setup(
+    mut commands: Commands,
+    drinks: Res<Drinks>,
+    bar_config: Res<BarConfig>,
+) {
+    spawn_controls_page(
+        &mut commands,
+        MenuButtonAction::Settings,
+        &drinks,
+        &bar_config,
+    );
+}
+
+/// Spawns the Controls page, with a "Back" button carrying `back_action` and a row for every barrel of `drinks`
+/// and every seat the bar can have. The binding texts are filled in by [`update_binding_texts`]
+pub fn spawn_controls_page<B: Component>(
+    commands: &mut Commands,
+    back_action: B,
+    drinks: &Drinks,
+    bar_config: &BarConfig,
+) {
+    let actions = GameAction::rebindable(drinks.iter().count(), bar_config.max_slot_count());
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(20.0),
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::FlexEnd,
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            OnControlsScreen,
+        ))
+        .with_children(|child_builder| {
+            child_builder.spawn(TextBundle::from_section(
+                "Controls",
+                TextStyle {
+                    font_size: 120.,
+                    color: TEXT_COLOR,
+                    ..Default::default()
+                },
+            ));
+        });
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(75.0),
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    align_self: AlignSelf::End,
+                    margin: UiRect::bottom(Val::Vh(5.)),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            OnControlsScreen,
+        ))
+        .with_children(|child_builder| {
+            let text_style = TextStyle {
+                font_size: 32.0,
+                color: TEXT_COLOR,
+                ..Default::default()
+            };
+            let slot_style = Style {
+                width: Val::Px(160.),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                padding: UiRect::axes(Val::Px(10.), Val::Px(5.)),
+                ..Default::default()
+            };
+
+            // The rows flow in columns
+            child_builder
+                .spawn(NodeBundle {
+                    style: Style {
+                        height: Val::Vh(50.),
+                        flex_direction: FlexDirection::Column,
+                        flex_wrap: FlexWrap::Wrap,
+                        align_content: AlignContent::Center,
+                        column_gap: Val::Px(60.),
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                })
+                .with_children(|child_builder| {
+                    for action in actions {
+                        spawn_binding_row(child_builder, action, &slot_style, &text_style);
+                    }
+                });
+
+            child_builder.spawn((
+                TextBundle::from_section("", text_style.clone()).with_style(Style {
+                    margin: UiRect::vertical(Val::Px(20.)),
+                    ..Default::default()
+                }),
+                ControlsNotice,
+            ));
+
+            let button_text_style = TextStyle {
+                font_size: 50.0,
+                ..text_style.clone()
+            };
+            child_builder
+                .spawn(NodeBundle {
+                    style: Style {
+                        column_gap: Val::Px(40.),
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                })
+                .with_children(|child_builder| {
+                    let button_style = Style {
+                        justify_content: JustifyContent::Center,
+                        align_items: AlignItems::Center,
+                        padding: UiRect::axes(Val::Px(15.), Val::Px(10.)),
+                        ..Default::default()
+                    };
+                    menu_button(
+                        child_builder,
+                        "Reset",
+                        ResetBindingsButton,
+                        &button_style,
+                        &ButtonColors {
+                            hovered: Color::rgb(0.5, 0.2, 0.2),
+                            ..Default::default()
+                        },
+                        &button_text_style,
+                    );
+                    menu_button(
+                        child_builder,
+                        "Back",
+                        back_action,
+                        &button_style,
+                        &ButtonColors::default(),
+                        &button_text_style,
+                    );
+                });
+        });
+}
+
+fn spawn_binding_row(
+    child_builder: &mut ChildBuilder,
+    action: GameAction,
+    slot_style: &Style,
+    text_style: &TextStyle,
+) {
+    child_builder
+        .spawn(NodeBundle {
+            style: Style {
+                align_items: AlignItems::Center,
+                column_gap: Val::Px(10.),
+                margin: UiRect::bottom(Val::Px(8.)),
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+        .with_children(|child_builder| {
+            child_builder.spawn(
+                TextBundle::from_section(action.label(), text_style.clone()).with_style(Style {
+                    width: Val::Px(200.),
+                    ..Default::default()
+                }),
+            );
+            for slot in 0..SLOTS {
+                menu_button(
+                    child_builder,
+                    "",
+                    BindingSlot { action, slot },
+                    slot_style,
+                    &ButtonColors::default(),
+                    text_style,
+                );
+            }
+        });
+}
+
+pub fn handle_binding_buttons(
+    slots_q: Query<(&Interaction, &BindingSlot), Changed<Interaction>>,
+    reset_q: Query<&Interaction, (Changed<Interaction>, With<ResetBindingsButton>)>,
+    mut rebinding: ResMut<Rebinding>,
+    mut key_capture: ResMut<KeyCapture>,
+    mut key_bindings: ResMut<KeyBindings>,
+) {
+    for (interaction, binding_slot) in &slots_q {
+        if *interaction == Interaction::Pressed {
+            rebinding.0 = Some(*binding_slot);
+            *key_capture = KeyCapture::Waiting;
+        }
+    }
+    for interaction in &reset_q {
+        if *interaction == Interaction::Pressed {
+            *key_bindings = KeyBindings::default();
+            rebinding.0 = None;
+            *key_capture = KeyCapture::Off;
+        }
+    }
+}
+
+/// Binds the keys captured for the slot waiting for them
+pub fn apply_key_capture(
+    mut rebinding: ResMut<Rebinding>,
+    mut key_capture: ResMut<KeyCapture>,
+    mut key_bindings: ResMut<KeyBindings>,
+) {
+    match *key_capture {
+        KeyCapture::Off | KeyCapture::Waiting => return,
+        KeyCapture::Cancelled => {}
+        KeyCapture::Captured { modifier, key } => {
+            if let Some(binding_slot) = rebinding.0 {
+                key_bindings.set(binding_slot.action, binding_slot.slot, modifier, key);
+            }
+        }
+    }
+    rebinding.0 = None;
+    *key_capture = KeyCapture::Off;
+}
+
+/// Shows the keys of every slot, the conflicts and the slot waiting for a key
+pub fn update_binding_texts(
+    slots_q: Query<(&BindingSlot, &Children)>,
+    mut texts_q: Query<&mut Text, Without<ControlsNotice>>,
+    mut notice_q: Query<&mut Text, With<ControlsNotice>>,
+    rebinding: Res<Rebinding>,
+    key_bindings: Res<KeyBindings>,
+) {
+    for (binding_slot, children) in &slots_q {
+        let Some(mut text) = children
+            .first()
+            .and_then(|child| texts_q.get_mut(*child).ok())
+        else {
+            continue;
+        };
+        let binding = key_bindings.get(binding_slot.action, binding_slot.slot);
+        let (value, color) = if rebinding.0 == Some(*binding_slot) {
+            ("...".to_string(), WAITING_COLOR)
+        } else {
+            match binding {
+                Some(binding) if key_bindings.conflicts(binding).next().is_some() => {
+                    (binding.keys_name(), CONFLICT_COLOR)
+                }
+                Some(binding) => (binding.keys_name(), TEXT_COLOR),
+                None => ("-".to_string(), TEXT_COLOR),
+            }
+        };
+        text.sections[0].value = value;
+        text.sections[0].style.color = color;
+    }
+
+    let mut notice = notice_q.single_mut();
+    notice.sections[0].value = if let Some(binding_slot) = rebinding.0 {
+        format!(
+            "Press a key for {}, Escape to cancel",
+            binding_slot.action.label()
+        )
+    } else if let Some((binding, other)) = key_bindings.iter().find_map(|binding| {
+        key_bindings
+            .conflicts(binding)
+            .next()
+            .map(|other| (binding, other))
+    }) {
+        format!(
+            "{} is bound to both {} and {}",
+            binding.keys_name(),
+            binding.action.label(),
+            other.action.label()
+        )
+    } else {
+        String::new()
+    };
+}
+
+/// Stops waiting for a key when leaving the page
+pub fn stop_key_capture(mut rebinding: ResMut<Rebinding>, mut key_capture: ResMut<KeyCapture>) {
+    rebinding.0 = None;
+    *key_capture = KeyCapture::Off;
+}
+
+pub fn esc_back_to_settings(
+    mut actions: ResMut<Actions>,
+    mut menu_state: ResMut<NextState<MenuState>>,
+) {
+    if actions.consume(GameAction::Back) {
+        menu_state.set(MenuState::Settings);
+    }
+}
@@ -1,15 +1,27 @@
+pub mod controls;
+mod mode_select;
+mod navigation;
 pub mod settings;
 
+use crate::actions::{Actions, ActionsSystem, GameAction};
+use crate::ingame::{IngameState, SaveGame, SessionStart};
 use crate::loading::TextureAssets;
 use crate::{
-    despawn_screen, exit_game_system, GameState, ScreenMode, CAMERA_RESOLUTION,
-    MENU_BACKGROUND_COLOR, TEXT_COLOR,
+    despawn_screen, exit_game_system, AdaptiveDifficulty, Difficulty, GameMode, GameState,
+    PourMode, ScreenMode, CAMERA_RESOLUTION, MENU_BACKGROUND_COLOR, TEXT_COLOR,
 };
 use bevy::prelude::*;
 use bevy::text::TextSettings;
 use bevy::time::Stopwatch;
+use bevy::ui::UiSystem;
 use bevy::window::WindowResized;
 
+use self::controls::{
+    apply_key_capture, controls_main_menu_setup, esc_back_to_settings, handle_binding_buttons,
+    stop_key_capture, update_binding_texts, OnControlsScreen, Rebinding,
+};
+use self::mode_select::{mode_select_setup, OnModeSelectScreen};
+use self::navigation::{navigate_buttons, ButtonFocus};
 use self::settings::{
     esc_back_to_main_menu, setting_button_handle, settings_button_colors, settings_main_menu_setup,
     OnSettingsMenuScreen,
@@ -24,7 +36,9 @@ struct OnMainMenuScreen;
 #[derive(Clone, Copy, Default, Eq, PartialEq, Debug, Hash, States)]
 pub enum MenuState {
     Main,
+    ModeSelect,
     Settings,
+    Controls,
     // SettingsDisplay,
     // SettingsSound,
     #[default]
@@ -42,6 +56,8 @@ impl Plugin for MenuPlugin {
                 ..Default::default()
             })
             .add_state::<MenuState>()
+            .init_resource::<Rebinding>()
+            .init_resource::<ButtonFocus>()
             .add_systems(OnEnter(GameState::Menu), (setup_menu_state, setup_camera))
             // MenuState::Main
             .add_systems(OnEnter(MenuState::Main), setup_main_menu)
@@ -55,13 +71,31 @@ impl Plugin for MenuPlugin {
                 ),
             )
             .add_systems(OnExit(MenuState::Main), despawn_screen::<OnMainMenuScreen>)
+            // MenuState::ModeSelect
+            .add_systems(OnEnter(MenuState::ModeSelect), mode_select_setup)
+            .add_systems(
+                Update,
+                esc_back_to_main_menu.run_if(in_state(MenuState::ModeSelect)),
+            )
+            .add_systems(
+                OnExit(MenuState::ModeSelect),
+                despawn_screen::<OnModeSelectScreen>,
+            )
             // MenuState::Settings
             .add_systems(OnEnter(MenuState::Settings), settings_main_menu_setup)
             .add_systems(
                 Update,
                 (
                     setting_button_handle::<ScreenMode>.run_if(in_state(MenuState::Settings)),
-                    settings_button_colors.run_if(in_state(MenuState::Settings)),
+                    setting_button_handle::<PourMode>.run_if(in_state(MenuState::Settings)),
+                    settings_button_colors::<ScreenMode>.run_if(in_state(MenuState::Settings)),
+                    settings_button_colors::<PourMode>.run_if(in_state(MenuState::Settings)),
+                    setting_button_handle::<Difficulty>.run_if(in_state(MenuState::Settings)),
+                    settings_button_colors::<Difficulty>.run_if(in_state(MenuState::Settings)),
+                    setting_button_handle::<AdaptiveDifficulty>
+                        .run_if(in_state(MenuState::Settings)),
+                    settings_button_colors::<AdaptiveDifficulty>
+                        .run_if(in_state(MenuState::Settings)),
                     esc_back_to_main_menu.run_if(in_state(MenuState::Settings)),
                 ),
             )
@@ -69,6 +103,23 @@ impl Plugin for MenuPlugin {
                 OnExit(MenuState::Settings),
                 despawn_screen::<OnSettingsMenuScreen>,
             )
+            // MenuState::Controls
+            .add_systems(OnEnter(MenuState::Controls), controls_main_menu_setup)
+            .add_systems(
+                Update,
+                (
+                    handle_binding_buttons,
+                    apply_key_capture,
+                    update_binding_texts,
+                    esc_back_to_settings,
+                )
+                    .chain()
+                    .run_if(in_state(MenuState::Controls)),
+            )
+            .add_systems(
+                OnExit(MenuState::Controls),
+                (despawn_screen::<OnControlsScreen>, stop_key_capture),
+            )
             // MenuState::Exit
             .add_systems(
                 OnEnter(MenuState::Exit),
@@ -76,6 +127,14 @@ impl Plugin for MenuPlugin {
             )
             // General
             .add_systems(Update, handle_buttons)
+            // Gamepad and keyboard navigation of the buttons, in the menus and the in-game screens
+            .add_systems(
+                PreUpdate,
+                navigate_buttons
+                    .after(ActionsSystem)
+                    .after(UiSystem::Focus)
+                    .run_if(not(in_state(IngameState::Running))),
+            )
             // Menu GameState exit
             .add_systems(OnExit(GameState::Menu), despawn_screen::<MainCameraMenu>);
     }
@@ -189,20 +248,36 @@ fn setup_main_menu(mut commands: Commands, textures: Res<TextureAssets>) {
                 ..Default::default()
             };
 
-            for (text, action, b_style, button_colors, button_text_style) in [
+            // Continue is greyed out if there is no save game to continue from
+            let (continue_button_colors, continue_text_style) = if SaveGame::exists() {
                 (
-                    "Continue",
-                    MenuButtonAction::Continue,
-                    &button_style,
-                    &ButtonColors {
+                    ButtonColors {
+                        hovered: Color::rgb(0.3, 0.4, 0.4),
+                        ..Default::default()
+                    },
+                    button_text_style.clone(),
+                )
+            } else {
+                (
+                    ButtonColors {
                         hovered: Color::rgb(0.4, 0.4, 0.4),
                         normal: Color::rgb(0.4, 0.4, 0.4),
                     },
-                    &TextStyle {
+                    TextStyle {
                         font_size: 50.0,
                         color: Color::rgb(0.6, 0.6, 0.6),
                         ..Default::default()
                     },
+                )
+            };
+
+            for (text, action, b_style, button_colors, button_text_style) in [
+                (
+                    "Continue",
+                    MenuButtonAction::Continue,
+                    &button_style,
+                    &continue_button_colors,
+                    &continue_text_style,
                 ),
                 (
                     "New Game",
@@ -373,7 +448,9 @@ pub fn menu_button<T: Component>(
 enum MenuButtonAction {
     Continue,
     Play,
+    StartGame(GameMode),
     Settings,
+    Controls,
     BackToMainMenu,
     Quit(bool),
 }
@@ -384,6 +461,8 @@ struct OpenLink(&'static str);
 fn handle_buttons(
     mut game_state: ResMut<NextState<GameState>>,
     mut menu_state: ResMut<NextState<MenuState>>,
+    mut session_start: ResMut<SessionStart>,
+    mut game_mode: ResMut<GameMode>,
     mut interaction_query: Query<
         (
             &Interaction,
@@ -402,12 +481,22 @@ fn handle_buttons(
             Interaction::Pressed => {
                 if let Some(mut action) = menu_button_action {
                     match *action {
-                        MenuButtonAction::Continue => {}
-                        MenuButtonAction::Play => {
+                        MenuButtonAction::Continue => {
+                            if SaveGame::exists() {
+                                *session_start = SessionStart::Continue;
+                                game_state.set(GameState::Playing);
+                                menu_state.set(MenuState::Disabled);
+                            }
+                        }
+                        MenuButtonAction::Play => menu_state.set(MenuState::ModeSelect),
+                        MenuButtonAction::StartGame(mode) => {
+                            *game_mode = mode;
+                            *session_start = SessionStart::NewGame;
                             game_state.set(GameState::Playing);
                             menu_state.set(MenuState::Disabled);
                         }
                         MenuButtonAction::Settings => menu_state.set(MenuState::Settings),
+                        MenuButtonAction::Controls => menu_state.set(MenuState::Controls),
                         MenuButtonAction::BackToMainMenu => menu_state.set(MenuState::Main),
                         MenuButtonAction::Quit(confirm) => {
                             if !confirm {
@@ -435,14 +524,21 @@ fn handle_buttons(
     }
 }
 
+/// Continues the save game right away. Without one, opens the game mode selection so a key press never replaces a save
 fn space_to_play(
-    keys: Res<Input<KeyCode>>,
+    actions: Res<Actions>,
     mut game_state: ResMut<NextState<GameState>>,
     mut menu_state: ResMut<NextState<MenuState>>,
+    mut session_start: ResMut<SessionStart>,
 ) {
-    if keys.just_pressed(KeyCode::Space) {
-        game_state.set(GameState::Playing);
-        menu_state.set(MenuState::Disabled);
+    if actions.just_pressed(GameAction::Confirm) {
+        if SaveGame::exists() {
+            *session_start = SessionStart::Continue;
+            game_state.set(GameState::Playing);
+            menu_state.set(MenuState::Disabled);
+        } else {
+            menu_state.set(MenuState::ModeSelect);
+        }
     }
 }
 
@@ -458,14 +554,14 @@ impl QuitEscTime {
 }
 
 fn esc_to_quit(
-    keys: Res<Input<KeyCode>>,
+    actions: Res<Actions>,
     mut quit_esc_time_query: Query<&mut QuitEscTime>,
     time: Res<Time>,
     mut menu_state: ResMut<NextState<MenuState>>,
     mut query_popup_visibility: Query<&mut Visibility, With<ExitPopup>>,
 ) {
     let mut quit_esc_time = quit_esc_time_query.single_mut();
-    if keys.just_pressed(KeyCode::Escape) {
+    if actions.just_pressed(GameAction::Back) {
         if let Some(stopw) = &quit_esc_time.time {
             if stopw.elapsed_secs() < 1. {
                 menu_state.set(MenuState::Exit);
@@ -0,0 +1,144 @@
+use bevy::prelude::*;
+
+use crate::{GameMode, TEXT_COLOR};
+
+use super::{menu_button, ButtonColors, MenuButtonAction};
+
+#[derive(Component)]
+pub struct OnModeSelectScreen;
+
+/// Game modes in the order they are listed, with their name and description
+const GAME_MODES: [(GameMode, &str, &str); 4] = [
+    (
+        GameMode::Endless,
+        "Endless",
+        "Day after day, the tavern never closes for good",
+    ),
+    (
+        GameMode::Career,
+        "Career",
+        "Last until the end of the career, every lost customer costs a life",
+    ),
+    (
+        GameMode::TimeAttack,
+        "Time Attack",
+        "Earn as much money as possible before the time is up",
+    ),
+    (
+        GameMode::Zen,
+        "Zen",
+        "No timers, customers wait as long as it takes",
+    ),
+];
+
+pub fn mode_select_setup(mut commands: Commands, game_mode: Res<GameMode>) {
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(25.0),
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::FlexEnd,
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            OnModeSelectScreen,
+        ))
+        .with_children(|child_builder| {
+            child_builder.spawn(TextBundle::from_section(
+                "Game Mode",
+                TextStyle {
+                    font_size: 120.,
+                    color: TEXT_COLOR,
+                    ..Default::default()
+                },
+            ));
+        });
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(65.0),
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    align_self: AlignSelf::End,
+                    margin: UiRect::bottom(Val::Vh(10.)),
+                    padding: UiRect::vertical(Val::Vh(10.)),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            OnModeSelectScreen,
+        ))
+        .with_children(|child_builder| {
+            let button_style = Style {
+                width: Val::Px(300.0),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                padding: UiRect::axes(Val::Px(15.), Val::Px(10.)),
+                ..Default::default()
+            };
+            let button_text_style = TextStyle {
+                font_size: 50.0,
+                color: TEXT_COLOR,
+                ..Default::default()
+            };
+            let description_text_style = TextStyle {
+                font_size: 30.0,
+                color: Color::rgb(0.6, 0.6, 0.6),
+                ..Default::default()
+            };
+
+            for (mode, name, description) in GAME_MODES {
+                // The mode of the last game is highlighted
+                let button_colors = if mode == *game_mode {
+                    ButtonColors {
+                        normal: Color::rgb(0.25, 0.35, 0.35),
+                        hovered: Color::rgb(0.3, 0.45, 0.45),
+                    }
+                } else {
+                    ButtonColors {
+                        hovered: Color::rgb(0.3, 0.4, 0.4),
+                        ..Default::default()
+                    }
+                };
+                menu_button(
+                    child_builder,
+                    name,
+                    MenuButtonAction::StartGame(mode),
+                    &button_style,
+                    &button_colors,
+                    &button_text_style,
+                );
+                child_builder.spawn(
+                    TextBundle::from_section(description, description_text_style.clone())
+                        .with_style(Style {
+                            margin: UiRect {
+                                top: Val::Px(5.),
+                                bottom: Val::Px(25.),
+                                ..Default::default()
+                            },
+                            ..Default::default()
+                        }),
+                );
+            }
+
+            menu_button(
+                child_builder,
+                "Back",
+                MenuButtonAction::BackToMainMenu,
+                &Style {
+                    margin: UiRect::top(Val::Px(50.)),
+                    ..button_style
+                },
+                &ButtonColors::default(),
+                &button_text_style,
+            );
+        });
+}
@@ -0,0 +1,102 @@
+//! Gamepad and keyboard navigation of the buttons.
+//!
+//! The `GameAction::Navigate*` actions move a focus between the visible buttons, to the closest one in the direction
+//! pressed, and `GameAction::Activate` presses the focused one. The focused button is shown as hovered and pressed
+//! through its `Interaction`, so every screen works without the mouse and without knowing about it. Moving the
+//! mouse drops the focus. While the game is running the navigation focuses the interactibles instead.
+
+use bevy::prelude::*;
+
+use crate::actions::{Actions, GameAction};
+
+/// Button focused with the gamepad or the keyboard
+#[derive(Resource, Default)]
+pub struct ButtonFocus(Option<Entity>);
+
+/// Runs after `UiSystem::Focus`, to override the `Interaction` it computed from the cursor
+pub fn navigate_buttons(
+    actions: Res<Actions>,
+    mut button_focus: ResMut<ButtonFocus>,
+    mut cursor_moved_events: EventReader<CursorMoved>,
+    mut buttons_q: Query<
+        (Entity, &GlobalTransform, &ViewVisibility, &mut Interaction),
+        With<Button>,
+    >,
+) {
+    if cursor_moved_events.read().count() > 0 {
+        // The mouse takes over
+        button_focus.0 = None;
+        return;
+    }
+
+    let buttons = buttons_q
+        .iter()
+        .filter(|(_, _, view_visibility, _)| view_visibility.get())
+        .map(|(entity, global_transform, _, _)| (entity, global_transform.translation().truncate()))
+        .collect::<Vec<_>>();
+    let focused = button_focus
+        .0
+        .and_then(|focused| buttons.iter().find(|(entity, _)| *entity == focused));
+
+    let direction = [
+        (GameAction::NavigateUp, Vec2::NEG_Y),
+        (GameAction::NavigateDown, Vec2::Y),
+        (GameAction::NavigateLeft, Vec2::NEG_X),
+        (GameAction::NavigateRight, Vec2::X),
+    ]
+    .into_iter()
+    .find(|(action, _)| actions.just_pressed(*action))
+    .map(|(_, direction)| direction);
+    let activate = actions.just_pressed(GameAction::Activate);
+
+    let new_focus = match (focused, direction) {
+        (Some((entity, position)), Some(direction)) => {
+            Some(closest_button(&buttons, *position, direction).unwrap_or(*entity))
+        }
+        (Some((entity, _)), None) => Some(*entity),
+        // The first press focuses the top left button
+        (None, _) if direction.is_some() || activate => buttons
+            .iter()
+            .min_by(|(_, a), (_, b)| a.y.total_cmp(&b.y).then(a.x.total_cmp(&b.x)))
+            .map(|(entity, _)| *entity),
+        (None, _) => None,
+    };
+
+    if let Some(old_focus) = button_focus
+        .0
+        .filter(|old_focus| Some(*old_focus) != new_focus)
+    {
+        if let Ok((_, _, _, mut interaction)) = buttons_q.get_mut(old_focus) {
+            interaction.set_if_neq(Interaction::None);
+        }
+    }
+    if let Some(new_focus) = new_focus {
+        if let Ok((_, _, _, mut interaction)) = buttons_q.get_mut(new_focus) {
+            // Pressing the button that was focused already
+            if activate && focused.is_some() {
+                *interaction = Interaction::Pressed;
+            } else {
+                interaction.set_if_neq(Interaction::Hovered);
+            }
+        }
+    }
+    button_focus.0 = new_focus;
+}
+
+/// Closest button from `position` in `direction`, favoring the ones in line with it
+fn closest_button(buttons: &[(Entity, Vec2)], position: Vec2, direction: Vec2) -> Option<Entity> {
+    buttons
+        .iter()
+        .filter_map(|(entity, button_position)| {
+            let offset = *button_position - position;
+            let along = offset.dot(direction);
+            // Buttons in the same row (or column) aren't in the direction
+            if along < 1. {
+                return None;
+            }
+            let across = (offset - direction * along).length();
+            Some((*entity, along + across * 2.))
+        })
+        .min_by(|(_, a), (_, b)| a.total_cmp(b))
+        .map(|(entity, _)| entity)
+}
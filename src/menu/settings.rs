@@ -1,6 +1,9 @@
+use std::fmt::Debug;
+
 use bevy::prelude::*;
 
-use crate::{ScreenMode, TEXT_COLOR};
+use crate::actions::{Actions, GameAction};
+use crate::{AdaptiveDifficulty, Difficulty, PourMode, ScreenMode, TEXT_COLOR};
 
 use super::{menu_button, ButtonColors, MenuButtonAction, MenuState};
 
@@ -16,7 +19,13 @@ const HOVERED_BUTTON: Color = Color::rgb(0.25, 0.25, 0.25);
 const HOVERED_PRESSED_BUTTON: Color = Color::rgb(0.25, 0.65, 0.25);
 const PRESSED_BUTTON: Color = Color::rgb(0.35, 0.75, 0.35);
 
-pub fn settings_main_menu_setup(mut commands: Commands, screen_mode: Res<ScreenMode>) {
+pub fn settings_main_menu_setup(
+    mut commands: Commands,
+    screen_mode: Res<ScreenMode>,
+    pour_mode: Res<PourMode>,
+    difficulty: Res<Difficulty>,
+    adaptive_difficulty: Res<AdaptiveDifficulty>,
+) {
     commands
         .spawn((
             NodeBundle {
@@ -84,57 +93,50 @@ pub fn settings_main_menu_setup(mut commands: Commands, screen_mode: Res<ScreenM
                 ..Default::default()
             };
 
-            child_builder
-                .spawn(NodeBundle {
-                    style: Style {
-                        align_items: AlignItems::Center,
-                        column_gap: Val::Px(15.),
-                        ..default()
-                    },
-                    ..default()
-                })
-                .with_children(|child_builder| {
-                    // Display a label for the current setting
-                    child_builder.spawn(
-                        TextBundle::from_section("Window Mode:", button_text_style.clone())
-                            .with_text_alignment(TextAlignment::Center)
-                            .with_style(Style {
-                                // width: Val::Px(300.0),
-                                // height: Val::Px(50.0),
-                                justify_content: JustifyContent::Center,
-                                align_items: AlignItems::Center,
-                                margin: UiRect {
-                                    right: Val::Px(35.),
-                                    bottom: Val::Px(25.),
-                                    ..Default::default()
-                                },
-                                // padding: UiRect::axes(Val::Px(15.), Val::Px(10.)),
-                                ..Default::default()
-                            }),
-                    );
-                    // Display a button for each possible value
-                    for screen_mode_setting in
-                        [ScreenMode::BorderlessFullscreen, ScreenMode::Windowed]
-                    {
-                        let mut entity = child_builder.spawn((
-                            ButtonBundle {
-                                style: button_style.clone(),
-                                background_color: NORMAL_BUTTON.into(),
-                                ..default()
-                            },
-                            screen_mode_setting,
-                        ));
-                        entity.with_children(|child_builder| {
-                            child_builder.spawn(TextBundle::from_section(
-                                format!("{screen_mode_setting:?}"),
-                                button_text_style.clone(),
-                            ));
-                        });
-                        if *screen_mode == screen_mode_setting {
-                            entity.insert(SelectedOption);
-                        }
-                    }
-                });
+            spawn_setting_row(
+                child_builder,
+                "Window Mode:",
+                &[ScreenMode::BorderlessFullscreen, ScreenMode::Windowed],
+                *screen_mode,
+                &button_style,
+                &button_text_style,
+            );
+            spawn_setting_row(
+                child_builder,
+                "Pouring:",
+                &[PourMode::Pour, PourMode::Instant],
+                *pour_mode,
+                &button_style,
+                &button_text_style,
+            );
+            spawn_setting_row(
+                child_builder,
+                "Difficulty:",
+                &[Difficulty::Easy, Difficulty::Normal, Difficulty::Hard],
+                *difficulty,
+                &button_style,
+                &button_text_style,
+            );
+            spawn_setting_row(
+                child_builder,
+                "Adaptive:",
+                &[AdaptiveDifficulty::Off, AdaptiveDifficulty::On],
+                *adaptive_difficulty,
+                &button_style,
+                &button_text_style,
+            );
+
+            menu_button(
+                child_builder,
+                "Controls",
+                MenuButtonAction::Controls,
+                &Style {
+                    margin: UiRect::top(Val::Px(25.)),
+                    ..button_style.clone()
+                },
+                &ButtonColors::default(),
+                &button_text_style,
+            );
 
             menu_button(
                 child_builder,
@@ -155,11 +157,71 @@ pub fn settings_main_menu_setup(mut commands: Commands, screen_mode: Res<ScreenM
         });
 }
 
-// This system handles changing all buttons color based on mouse interaction
-pub fn settings_button_colors(
+/// Spawns a labeled row with a button for every option of the setting `T`, `current` being selected
+pub fn spawn_setting_row<T: Component + Debug + PartialEq + Copy>(
+    child_builder: &mut ChildBuilder,
+    label: &str,
+    options: &[T],
+    current: T,
+    button_style: &Style,
+    button_text_style: &TextStyle,
+) {
+    child_builder
+        .spawn(NodeBundle {
+            style: Style {
+                align_items: AlignItems::Center,
+                column_gap: Val::Px(15.),
+                ..default()
+            },
+            ..default()
+        })
+        .with_children(|child_builder| {
+            // Display a label for the current setting
+            child_builder.spawn(
+                TextBundle::from_section(label, button_text_style.clone())
+                    .with_text_alignment(TextAlignment::Center)
+                    .with_style(Style {
+                        // width: Val::Px(300.0),
+                        // height: Val::Px(50.0),
+                        justify_content: JustifyContent::Center,
+                        align_items: AlignItems::Center,
+                        margin: UiRect {
+                            right: Val::Px(35.),
+                            bottom: Val::Px(25.),
+                            ..Default::default()
+                        },
+                        // padding: UiRect::axes(Val::Px(15.), Val::Px(10.)),
+                        ..Default::default()
+                    }),
+            );
+            // Display a button for each possible value
+            for option in options.iter().copied() {
+                let mut entity = child_builder.spawn((
+                    ButtonBundle {
+                        style: button_style.clone(),
+                        background_color: NORMAL_BUTTON.into(),
+                        ..default()
+                    },
+                    option,
+                ));
+                entity.with_children(|child_builder| {
+                    child_builder.spawn(TextBundle::from_section(
+                        format!("{option:?}"),
+                        button_text_style.clone(),
+                    ));
+                });
+                if current == option {
+                    entity.insert(SelectedOption);
+                }
+            }
+        });
+}
+
+// This system handles changing all buttons color of the setting `T` based on mouse interaction
+pub fn settings_button_colors<T: Component>(
     mut interaction_query: Query<
         (&Interaction, &mut BackgroundColor, Option<&SelectedOption>),
-        (With<Interaction>, (With<Button>, With<ScreenMode>)),
+        (With<Interaction>, (With<Button>, With<T>)),
     >,
 ) {
     for (interaction, mut color, selected) in &mut interaction_query {
@@ -174,7 +236,7 @@ pub fn settings_button_colors(
 
 pub fn setting_button_handle<T: Resource + Component + PartialEq + Copy>(
     interaction_query: Query<(&Interaction, &T, Entity), (With<Interaction>, With<Button>)>,
-    mut selected_query: Query<(Entity, &mut BackgroundColor), With<SelectedOption>>,
+    mut selected_query: Query<(Entity, &mut BackgroundColor), (With<SelectedOption>, With<T>)>,
     mut commands: Commands,
     mut setting: ResMut<T>,
 ) {
@@ -194,11 +256,10 @@ pub fn setting_button_handle<T: Resource + Component + PartialEq + Copy>(
 }
 
 pub fn esc_back_to_main_menu(
-    mut keys: ResMut<Input<KeyCode>>,
+    mut actions: ResMut<Actions>,
     mut menu_state: ResMut<NextState<MenuState>>,
 ) {
-    if keys.just_pressed(KeyCode::Escape) {
-        keys.reset(KeyCode::Escape);
+    if actions.consume(GameAction::Back) {
         menu_state.set(MenuState::Main);
     }
 }
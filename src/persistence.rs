@@ -0,0 +1,192 @@
+//! Small key/value storage used to persist game data (like the save game or the settings) between launches.
+//!
+//! Values are serialized as RON. On desktop every key is a file inside the platform data or config directory,
+//! on mobile it is a file in the app storage and on the web build (wasm) every key is an entry
+//! in the browser `localStorage`.
+
+use serde::{de::DeserializeOwned, Serialize};
+use thiserror::Error;
+
+/// Name used for the game folder or as the prefix of the `localStorage` keys.
+const APP_NAME: &str = "tavern";
+
+/// Where a value is stored. Only makes a difference on desktop platforms.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Location {
+    /// Game data, like the save game
+    Data,
+    /// User preferences, like the settings
+    Config,
+}
+
+#[derive(Debug, Error)]
+pub enum PersistenceError {
+    #[error("no storage available on this platform")]
+    Unavailable,
+    #[error("io error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("storage error: {0}")]
+    #[cfg_attr(not(target_arch = "wasm32"), allow(dead_code))]
+    Storage(String),
+    #[error("failed to serialize: {0}")]
+    Serialize(#[from] ron::Error),
+    #[error("failed to deserialize: {0}")]
+    Deserialize(#[from] ron::error::SpannedError),
+}
+
+/// Serializes `value` and stores it under `key`, replacing the previous value.
+pub fn write<T: Serialize>(
+    location: Location,
+    key: &str,
+    value: &T,
+) -> Result<(), PersistenceError> {
+    let serialized = ron::ser::to_string_pretty(value, ron::ser::PrettyConfig::default())?;
+    backend::write_string(location, key, &serialized)
+}
+
+/// Reads and deserializes the value stored under `key`. Returns `Ok(None)` if there is nothing stored.
+pub fn read<T: DeserializeOwned>(
+    location: Location,
+    key: &str,
+) -> Result<Option<T>, PersistenceError> {
+    match backend::read_string(location, key)? {
+        Some(serialized) => Ok(Some(ron::from_str(&serialized)?)),
+        None => Ok(None),
+    }
+}
+
+/// Stores a copy of the value stored under `from` under `to`, without deserializing it. Does nothing if there is
+/// nothing stored under `from`.
+pub fn copy(location: Location, from: &str, to: &str) -> Result<(), PersistenceError> {
+    match backend::read_string(location, from)? {
+        Some(serialized) => backend::write_string(location, to, &serialized),
+        None => Ok(()),
+    }
+}
+
+/// Deletes the value stored under `key`, if any.
+pub fn remove(location: Location, key: &str) -> Result<(), PersistenceError> {
+    backend::remove(location, key)
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+mod backend {
+    use std::path::PathBuf;
+
+    use super::{Location, PersistenceError, APP_NAME};
+
+    #[cfg(target_os = "android")]
+    fn base_dir(_location: Location) -> Option<PathBuf> {
+        bevy::winit::ANDROID_APP
+            .get()
+            .and_then(|android_app| android_app.internal_data_path())
+            .map(|internal_data_path| internal_data_path.join(APP_NAME))
+    }
+
+    #[cfg(target_os = "windows")]
+    fn base_dir(_location: Location) -> Option<PathBuf> {
+        std::env::var_os("APPDATA").map(|app_data| PathBuf::from(app_data).join(APP_NAME))
+    }
+
+    #[cfg(any(target_os = "macos", target_os = "ios"))]
+    fn base_dir(location: Location) -> Option<PathBuf> {
+        let dir = match location {
+            Location::Data => "Library/Application Support",
+            Location::Config => "Library/Preferences",
+        };
+        std::env::var_os("HOME").map(|home| PathBuf::from(home).join(dir).join(APP_NAME))
+    }
+
+    #[cfg(not(any(
+        target_os = "android",
+        target_os = "windows",
+        target_os = "macos",
+        target_os = "ios"
+    )))]
+    fn base_dir(location: Location) -> Option<PathBuf> {
+        let (xdg_var, home_fallback) = match location {
+            Location::Data => ("XDG_DATA_HOME", ".local/share"),
+            Location::Config => ("XDG_CONFIG_HOME", ".config"),
+        };
+        std::env::var_os(xdg_var)
+            .map(PathBuf::from)
+            .or_else(|| {
+                std::env::var_os("HOME").map(|home| PathBuf::from(home).join(home_fallback))
+            })
+            .map(|base| base.join(APP_NAME))
+    }
+
+    fn path_for(location: Location, key: &str) -> Result<PathBuf, PersistenceError> {
+        base_dir(location)
+            .map(|dir| dir.join(format!("{key}.ron")))
+            .ok_or(PersistenceError::Unavailable)
+    }
+
+    pub fn write_string(
+        location: Location,
+        key: &str,
+        value: &str,
+    ) -> Result<(), PersistenceError> {
+        let path = path_for(location, key)?;
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        // Write to a temporary file first so a crash never leaves a half written file behind
+        let tmp_path = path.with_extension("ron.tmp");
+        std::fs::write(&tmp_path, value)?;
+        std::fs::rename(tmp_path, path)?;
+        Ok(())
+    }
+
+    pub fn read_string(location: Location, key: &str) -> Result<Option<String>, PersistenceError> {
+        match std::fs::read_to_string(path_for(location, key)?) {
+            Ok(value) => Ok(Some(value)),
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(error) => Err(error.into()),
+        }
+    }
+
+    pub fn remove(location: Location, key: &str) -> Result<(), PersistenceError> {
+        match std::fs::remove_file(path_for(location, key)?) {
+            Err(error) if error.kind() != std::io::ErrorKind::NotFound => Err(error.into()),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+mod backend {
+    use super::{Location, PersistenceError, APP_NAME};
+
+    fn local_storage() -> Result<web_sys::Storage, PersistenceError> {
+        web_sys::window()
+            .and_then(|window| window.local_storage().ok().flatten())
+            .ok_or(PersistenceError::Unavailable)
+    }
+
+    fn storage_key(key: &str) -> String {
+        format!("{APP_NAME}.{key}")
+    }
+
+    pub fn write_string(
+        _location: Location,
+        key: &str,
+        value: &str,
+    ) -> Result<(), PersistenceError> {
+        local_storage()?
+            .set_item(&storage_key(key), value)
+            .map_err(|error| PersistenceError::Storage(format!("{error:?}")))
+    }
+
+    pub fn read_string(_location: Location, key: &str) -> Result<Option<String>, PersistenceError> {
+        local_storage()?
+            .get_item(&storage_key(key))
+            .map_err(|error| PersistenceError::Storage(format!("{error:?}")))
+    }
+
+    pub fn remove(_location: Location, key: &str) -> Result<(), PersistenceError> {
+        local_storage()?
+            .remove_item(&storage_key(key))
+            .map_err(|error| PersistenceError::Storage(format!("{error:?}")))
+    }
+}